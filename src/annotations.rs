@@ -0,0 +1,187 @@
+//!
+//! Annotations: the `lhs::` tool attributes a crate marks its own sources, sinks, sanitizers
+//! and reviewed code with
+//!
+//! ```ignore
+//! #[cfg_attr(lhs, lhs::source)]
+//! fn read_request() -> String { ... }
+//!
+//! #[cfg_attr(lhs, lhs::sink(arg = 0, forbid = "/proc/*/mem"))]
+//! fn store(path: &str, data: &[u8]) { ... }
+//!
+//! #[cfg_attr(lhs, lhs::sanitizer)]
+//! fn confine(path: &str) -> String { ... }
+//!
+//! #[cfg_attr(lhs, lhs::allow(reason = "the path is a constant"))]
+//! store(CACHE, &data);
+//! ```
+//!
+//! LHS compiles the crates it analyzes with `--cfg lhs` and registers the `lhs` tool, so the
+//! attributes are only there when LHS runs. The annotated functions are registered as
+//! handlers next to the ones of the settings, for calls to exactly that function, while the
+//! paths of the settings also match the functions under them. `lhs::allow` suppresses the
+//! findings at the statement, expression or item it is on.
+//!
+use rustc_ast::LitKind;
+use rustc_hir::def_id::{DefId, LOCAL_CRATE, StableCrateId};
+use rustc_hir::{Attribute, HirId};
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+
+use crate::parser::SinkHit;
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+/// The annotations of the crate being compiled
+#[derive(Debug, Default)]
+pub struct Annotations {
+    /// Functions whose result is tainted
+    pub sources: Vec<DefId>,
+    /// (function, argument, forbidden value) for each `lhs::sink`
+    pub sinks: Vec<(DefId, usize, String)>,
+    /// Functions whose result is never tainted
+    pub sanitizers: Vec<DefId>,
+    /// Code under an `lhs::allow`
+    pub allowed: Vec<Span>,
+}
+
+impl Annotations {
+    /// Whether `hit` is suppressed: the sink call, or one of the calls that lead to it, is in
+    /// code under an `lhs::allow`
+    pub fn allows(&self, hit: &SinkHit) -> bool {
+        std::iter::once(&hit.span)
+            .chain(&hit.call_spans)
+            .any(|span| self.allowed.iter().any(|allowed| allowed.contains(*span)))
+    }
+}
+
+/// The annotations of each crate compiled by this process so far
+static ANNOTATIONS: OnceLock<Mutex<HashMap<StableCrateId, &'static Annotations>>> =
+    OnceLock::new();
+
+/// The annotations of the local crate, read from its HIR the first time they are needed.
+/// Malformed annotations are reported as errors.
+pub fn annotations(tcx: TyCtxt<'_>) -> &'static Annotations {
+    let crate_id = tcx.stable_crate_id(LOCAL_CRATE);
+    let mut annotations = ANNOTATIONS.get_or_init(Default::default).lock().unwrap();
+    annotations
+        .entry(crate_id)
+        .or_insert_with(|| Box::leak(Box::new(read_annotations(tcx))))
+}
+
+fn read_annotations(tcx: TyCtxt<'_>) -> Annotations {
+    let mut annotations = Annotations::default();
+    for owner in tcx.hir_crate_items(()).owners() {
+        for &(local_id, attrs) in tcx.hir_attr_map(owner).map.iter() {
+            let hir_id = HirId { owner, local_id };
+            for attr in attrs {
+                if let Some(name) = lhs_attr_name(attr) {
+                    read_annotation(tcx, hir_id, attr, &name, &mut annotations);
+                }
+            }
+        }
+    }
+    annotations
+}
+
+/// `name` for an `lhs::name` attribute
+fn lhs_attr_name(attr: &Attribute) -> Option<String> {
+    match attr.path().as_slice() {
+        [tool, name] if tool.as_str() == "lhs" => Some(name.to_string()),
+        _ => None,
+    }
+}
+
+fn read_annotation(
+    tcx: TyCtxt<'_>,
+    hir_id: HirId,
+    attr: &Attribute,
+    name: &str,
+    annotations: &mut Annotations,
+) {
+    let dcx = tcx.dcx();
+    if name == "allow" {
+        match attr_value(attr, "reason") {
+            Some(_) => annotations.allowed.push(tcx.hir_span_with_body(hir_id)),
+            None => {
+                dcx.span_err(attr.span(), "`lhs::allow` needs a `reason = \"...\"`");
+            }
+        }
+        return;
+    }
+    // the other annotations are on functions
+    let Some(def_id) = annotated_fn(tcx, hir_id) else {
+        dcx.span_err(
+            attr.span(),
+            format!("`lhs::{name}` can only be put on a function"),
+        );
+        return;
+    };
+    match name {
+        "source" => annotations.sources.push(def_id),
+        "sanitizer" => annotations.sanitizers.push(def_id),
+        "sink" => {
+            let Some(forbidden) = attr_value(attr, "forbid") else {
+                dcx.span_err(attr.span(), "`lhs::sink` needs a `forbid = \"...\"`");
+                return;
+            };
+            let arg = match attr_arg(attr) {
+                Ok(arg) => arg.unwrap_or(0),
+                Err(()) => {
+                    dcx.span_err(
+                        attr.span(),
+                        "`arg` of `lhs::sink` must be an argument index",
+                    );
+                    return;
+                }
+            };
+            annotations.sinks.push((def_id, arg, forbidden));
+        }
+        _ => {
+            dcx.span_err(
+                attr.span(),
+                format!(
+                    "unknown annotation `lhs::{name}`, expected `source`, `sink`, `sanitizer` \
+                     or `allow`"
+                ),
+            );
+        }
+    }
+}
+
+/// The function or method `hir_id` is, if it is one
+fn annotated_fn(tcx: TyCtxt<'_>, hir_id: HirId) -> Option<DefId> {
+    let def_id = hir_id.as_owner()?.to_def_id();
+    tcx.def_kind(def_id).is_fn_like().then_some(def_id)
+}
+
+/// The string value of `key` in an attribute like `lhs::sink(key = "value")`
+fn attr_value(attr: &Attribute, key: &str) -> Option<String> {
+    attr.meta_item_list()?
+        .iter()
+        .find(|item| item.name().is_some_and(|name| name.as_str() == key))
+        .and_then(|item| item.value_str())
+        .map(|value| value.to_string())
+}
+
+/// The `arg = <index>` of an `lhs::sink`, or `None` without one
+fn attr_arg(attr: &Attribute) -> Result<Option<usize>, ()> {
+    let Some(items) = attr.meta_item_list() else {
+        return Ok(None);
+    };
+    let Some(item) = items
+        .iter()
+        .find(|item| item.name().is_some_and(|name| name.as_str() == "arg"))
+    else {
+        return Ok(None);
+    };
+    match item
+        .meta_item()
+        .and_then(|m| m.name_value_literal())
+        .map(|lit| &lit.kind)
+    {
+        Some(LitKind::Int(n, _)) => usize::try_from(n.get()).map(Some).map_err(|_| ()),
+        _ => Err(()),
+    }
+}
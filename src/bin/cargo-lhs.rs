@@ -0,0 +1,264 @@
+//!
+//! `cargo lhs`: runs the `lhs` driver over a cargo project
+//!
+//! Sets `RUSTC_WRAPPER` to the `lhs` binary that sits next to this one, forces the selected
+//! packages to rebuild, and prints one report of what every crate found. Nothing in the
+//! target project (`.cargo/config.toml`, manifests) is touched.
+//!
+//! Usage: `cargo lhs [-p <package>]... [--sarif <FILE>] [--out-dir <DIR>] [--config <FILE>] [LHS FLAGS]... [-- <cargo build args>...]`
+//!
+// This binary deliberately does not link against the `lhs` library: the library pulls in
+// `rustc_private`, and `cargo lhs` runs under whatever toolchain the target project uses.
+#[path = "../cli.rs"]
+#[allow(dead_code)]
+mod cli;
+#[path = "../report.rs"]
+#[allow(dead_code)]
+mod report;
+#[path = "../sarif.rs"]
+mod sarif;
+#[path = "../settings.rs"]
+#[allow(dead_code)]
+mod settings;
+#[path = "../summary.rs"]
+#[allow(dead_code)]
+mod summary;
+
+use clap::Parser;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+
+use cli::{Args, LHS_ARGS_ENV};
+use report::{Location, Report, Tier};
+
+const USAGE: &str = "Usage: cargo lhs [-p <package>]... [--sarif <FILE>] [--out-dir <DIR>] [--config <FILE>] [LHS FLAGS]... [-- <cargo build args>...]
+
+Runs the LHS analysis over the selected packages (default: every workspace member) and
+prints one report at the end. `--sarif` also writes the findings as a SARIF 2.1.0 log.
+`--out-dir` holds the per-crate reports (default: `target/lhs`), and `--config` is the
+settings file (default: `LHS_CONFIG`, or the closest `lhs.toml`).
+LHS FLAGS are the flags of the `lhs` driver, see `lhs --help`.";
+
+fn main() {
+    // cargo runs us as `cargo-lhs lhs <args>...`
+    let mut argv = env::args().skip(1).peekable();
+    if argv.peek().map(String::as_str) == Some("lhs") {
+        argv.next();
+    }
+
+    let mut packages = Vec::new();
+    let mut sarif = None;
+    let mut out_dir = None;
+    let mut config = None;
+    let mut lhs_args = Vec::new();
+    let mut cargo_args = Vec::new();
+    while let Some(arg) = argv.next() {
+        // `--flag value` and `--flag=value`
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| argv.next())
+                .unwrap_or_else(|| fail(&format!("`{flag}` expects a value")))
+        };
+        match flag {
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return;
+            }
+            "-p" | "--package" => packages.push(value()),
+            "--sarif" => sarif = Some(PathBuf::from(value())),
+            // The driver runs in other directories, it gets these as absolute paths
+            "--out-dir" => out_dir = Some(PathBuf::from(value())),
+            "--config" => config = Some(PathBuf::from(value())),
+            "--" => {
+                cargo_args.extend(argv.by_ref());
+            }
+            _ => lhs_args.push(arg),
+        }
+    }
+
+    // Reject bad driver flags here rather than in the middle of the build
+    let args =
+        Args::try_parse_from(std::iter::once("lhs").chain(lhs_args.iter().map(String::as_str)))
+            .unwrap_or_else(|e| e.exit());
+
+    // Dependencies are built outside of the project, hand them the project's settings
+    let config = settings::config_path(config.as_deref()).map(|path| absolute(&path));
+    let settings = settings::Settings::discover(config.as_deref()).unwrap_or_else(|e| fail(&e));
+    // The driver would reject a misspelled sink for every crate, and the build with it
+    for sink in args.named_sinks() {
+        if !settings.sink_patterns().iter().any(|(path, _, _)| *path == sink) {
+            fail(&format!("`{sink}` is not a configured sink, expected `all` or a sink path"));
+        }
+    }
+
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let driver = lhs_driver();
+    let metadata = cargo_metadata(&cargo);
+    if packages.is_empty() {
+        packages = workspace_packages(&metadata);
+    }
+
+    let out_dir = out_dir.unwrap_or_else(|| {
+        PathBuf::from(metadata["target_directory"].as_str().unwrap_or("target")).join("lhs")
+    });
+    if let Err(e) = fs::create_dir_all(&out_dir) {
+        fail(&format!("could not create {}: {e}", out_dir.display()));
+    }
+    let out_dir = out_dir.canonicalize().unwrap_or(out_dir);
+    remove_reports(&out_dir);
+
+    // Only the crates we analyze are cleaned, the driver passes every other dependency
+    // straight through to rustc so those can stay cached. Summarized dependencies all go
+    // through the driver again, along with their summaries from an earlier run.
+    let cleans: Vec<Vec<&str>> = if args.include_deps || args.summarize_deps {
+        let _ = fs::remove_dir_all(out_dir.join(summary::SUMMARY_DIR));
+        vec![vec!["clean"]]
+    } else {
+        packages
+            .iter()
+            .chain(&args.deps)
+            .map(|p| vec!["clean", "-p", p.as_str()])
+            .collect()
+    };
+    for clean in cleans {
+        let status = cargo_command(&cargo).args(&clean).status();
+        if !status.is_ok_and(|s| s.success()) {
+            fail(&format!("`cargo {}` failed", clean.join(" ")));
+        }
+    }
+
+    let mut build = cargo_command(&cargo);
+    build
+        .arg("build")
+        .args(packages.iter().flat_map(|p| ["-p", p.as_str()]))
+        .args(&cargo_args)
+        .env("RUSTC_WRAPPER", &driver)
+        .env(LHS_ARGS_ENV, lhs_args.join(" "))
+        .env("LHS_OUT_DIR", &out_dir);
+    if let Some(config) = &config {
+        build.env(settings::CONFIG_ENV, config);
+    }
+    let status = build
+        .status()
+        .unwrap_or_else(|e| fail(&format!("could not run cargo: {e}")));
+
+    let reports = report::merge_reports(&out_dir)
+        .unwrap_or_else(|e| fail(&format!("could not read the LHS reports: {e}")));
+    print_report(&reports, packages.len());
+    if let Some(sarif) = sarif {
+        let log = sarif::sarif_log(&reports, &settings.sink_patterns(), |sink| {
+            args.sink_level(sink)
+        });
+        let json = serde_json::to_string_pretty(&log).expect("a SARIF log is valid JSON");
+        if let Err(e) = fs::write(&sarif, json) {
+            fail(&format!("could not write {}: {e}", sarif.display()));
+        }
+    }
+    process::exit(status.code().unwrap_or(1));
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path)
+        .unwrap_or_else(|e| fail(&format!("invalid path {}: {e}", path.display())))
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("error: {msg}");
+    process::exit(1);
+}
+
+// The `lhs` driver is installed next to `cargo-lhs`
+fn lhs_driver() -> PathBuf {
+    let exe =
+        env::current_exe().unwrap_or_else(|e| fail(&format!("could not locate cargo-lhs: {e}")));
+    let driver = exe.with_file_name(format!("lhs{}", env::consts::EXE_SUFFIX));
+    if !driver.exists() {
+        fail(&format!(
+            "could not find the lhs driver at {}",
+            driver.display()
+        ));
+    }
+    driver
+}
+
+// The driver links against the toolchain it was built with, so the target project has to
+// be compiled with that same toolchain
+fn cargo_command(cargo: &str) -> Command {
+    let mut cmd = Command::new(cargo);
+    if let Some(toolchain) = option_env!("RUSTUP_TOOLCHAIN") {
+        cmd.env("RUSTUP_TOOLCHAIN", toolchain);
+    }
+    cmd
+}
+
+fn cargo_metadata(cargo: &str) -> serde_json::Value {
+    let out = cargo_command(cargo)
+        .args(["metadata", "--no-deps", "--format-version", "1"])
+        .output()
+        .unwrap_or_else(|e| fail(&format!("could not run cargo metadata: {e}")));
+    if !out.status.success() {
+        eprint!("{}", String::from_utf8_lossy(&out.stderr));
+        fail("`cargo metadata` failed");
+    }
+    serde_json::from_slice(&out.stdout)
+        .unwrap_or_else(|e| fail(&format!("could not parse cargo metadata: {e}")))
+}
+
+// With `--no-deps`, `packages` holds exactly the workspace members
+fn workspace_packages(metadata: &serde_json::Value) -> Vec<String> {
+    metadata["packages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|p| p["name"].as_str().map(String::from))
+        .collect()
+}
+
+// Reports of an earlier run must not end up in this one. Only reports go, the directory can
+// be any directory of the user's.
+fn remove_reports(out_dir: &Path) {
+    let Ok(entries) = fs::read_dir(out_dir) else {
+        return;
+    };
+    for path in entries.filter_map(|e| e.ok().map(|e| e.path())) {
+        if path
+            .file_name()
+            .is_some_and(|n| report::is_report_file_name(&n.to_string_lossy()))
+        {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+fn print_report(reports: &[Report], package_count: usize) {
+    let mut findings: BTreeMap<(&str, &str), Vec<(&Location, Tier)>> = BTreeMap::new();
+    for finding in reports.iter().flat_map(|r| &r.findings) {
+        findings
+            .entry((&finding.sink, &finding.forbidden))
+            .or_default()
+            .push((&finding.location, finding.tier));
+    }
+
+    println!();
+    println!("=== LHS report ===");
+    let mut total = 0;
+    for ((sink, forbidden), locations) in &mut findings {
+        // the strongest tier of a location comes first
+        locations.sort();
+        locations.dedup_by_key(|(location, _)| *location);
+        println!("{sink} (arg = {forbidden:?})");
+        for (location, tier) in locations.iter() {
+            println!("    {location} ({tier})");
+        }
+        total += locations.len();
+    }
+    println!("{total} finding(s) in {package_count} package(s)");
+}
@@ -1,4 +1,5 @@
 extern crate rustc_driver;
+extern crate rustc_errors;
 extern crate rustc_interface;
 extern crate rustc_middle;
 extern crate rustc_session;
@@ -7,43 +8,128 @@ extern crate rustc_span;
 extern crate rustc_data_structures;
 extern crate rustc_metadata;
 
+use rustc_ast::{self as ast, AttrStyle, Safety, attr};
 use rustc_data_structures::steal::Steal;
 use rustc_data_structures::sync::{MappedReadGuard, ReadGuard, RwLock};
 use rustc_driver::{Callbacks, Compilation};
+use rustc_expand::config::pre_configure_attrs;
+use rustc_errors::Diag;
 use rustc_hir::def::DefKind;
-use rustc_interface::interface::Compiler;
-use rustc_middle::mir::Body;
+use rustc_hir::def_id::{DefId, LOCAL_CRATE, LocalDefId};
+use rustc_interface::interface::{Compiler, Config};
+use rustc_middle::mir::{Body, VarDebugInfoContents};
+use rustc_middle::mir::mono::MonoItem;
+use rustc_middle::ty::InstanceKind;
+use rustc_middle::mir::pretty::{PrettyPrintMirOptions, write_mir_fn};
 use rustc_middle::ty::{TyCtxt, TyKind};
 use rustc_span::FileNameDisplayPreference;
 
 use rustc_middle::query::LocalCrate;
 use rustc_middle::util::Providers;
 use rustc_session::search_paths::PathKind;
-use rustc_span::Span;
+use rustc_span::{DUMMY_SP, Span, Symbol, sym};
 
-use crate::parser::MIRParser;
+use crate::annotations::annotations;
+use crate::cli::{Action, Args, Level};
+use crate::parser::{MIRParser, SinkHit, declare_locals, instance_body, merge_hit, summary_key};
+use crate::report::{Report, Tier, report_file_name, write_report};
+use crate::settings::{path_matches, settings};
+use crate::summary::{
+    ArgSink, CrateSummary, FunctionSummary, Summaries, read_summary, write_summary,
+};
 use rustc_span::source_map::SourceMap;
 // use crate::symexec;
 use crate::symexec::SymExecBool as SymExec;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::fs::OpenOptions;
 use std::io::Write;
 use std::io::{BufWriter, Write as _};
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
-const DEF_ID_PATH_BUF: usize = 5175;
+pub struct LCallback {
+    args: Args,
+    /// Stop once the analysis is done instead of going on to codegen
+    stop_after_analysis: bool,
+    /// Report the hits, as opposed to only writing the crate's summary
+    report: bool,
+}
+
+/// Callbacks for crates LHS does not analyze: rustc runs exactly as it would without us
+pub struct PassThrough;
 
-pub struct LCallback {}
+impl Callbacks for PassThrough {}
 
 impl LCallback {
-    pub fn new() -> Self {
-        LCallback {}
+    pub fn new(args: Args) -> Self {
+        LCallback {
+            args,
+            stop_after_analysis: false,
+            report: true,
+        }
+    }
+
+    /// Callbacks for a dependency we do not analyze but that the analyzed crates call into
+    /// (`--summarize-deps`): only its summary is written.
+    pub fn summarize_only(args: Args) -> Self {
+        LCallback {
+            args,
+            stop_after_analysis: false,
+            report: false,
+        }
+    }
+
+    /// Callbacks for a rustc session we set up ourselves (`lhs --source <FILE>`): nobody needs
+    /// the compiled artifact, so the compilation stops right after the analysis.
+    pub fn standalone(args: Args) -> Self {
+        LCallback {
+            args,
+            stop_after_analysis: true,
+            report: true,
+        }
     }
 }
 
 impl Callbacks for LCallback {
+    // The crates we analyze are compiled with `--cfg lhs` and know the `lhs` tool, so their
+    // `#[cfg_attr(lhs, lhs::...)]` annotations are there for us to read
+    fn config(&mut self, config: &mut Config) {
+        config.crate_cfg.push("lhs".to_string());
+        if !config.crate_check_cfg.is_empty() {
+            config.crate_check_cfg.push("cfg(lhs)".to_string());
+        }
+    }
+    // The tool is registered in the crate root, unless the crate enables the feature or
+    // registers the tool itself, which must not happen twice
+    fn after_crate_root_parsing(
+        &mut self,
+        compiler: &Compiler,
+        krate: &mut ast::Crate,
+    ) -> Compilation {
+        let lhs = Symbol::intern("lhs");
+        // the crate attributes `--cfg lhs` leaves, as the compiler reads them
+        let attrs = pre_configure_attrs(&compiler.sess, &krate.attrs);
+        let has_item = |name, item| {
+            attr::filter_by_name(&attrs, name).any(|attr| {
+                attr.meta_item_list()
+                    .is_some_and(|items| items.iter().any(|i| i.has_name(item)))
+            })
+        };
+        let ids = &compiler.sess.psess.attr_id_generator;
+        let crate_attr = |outer, inner| {
+            let (style, safety) = (AttrStyle::Inner, Safety::Default);
+            attr::mk_attr_nested_word(ids, style, safety, outer, inner, DUMMY_SP)
+        };
+        if !has_item(sym::feature, sym::register_tool) {
+            krate.attrs.push(crate_attr(sym::feature, sym::register_tool));
+        }
+        if !has_item(sym::register_tool, lhs) {
+            krate.attrs.push(crate_attr(sym::register_tool, lhs));
+        }
+        Compilation::Continue
+    }
     // fn config(&mut self, config: &mut Config) {
     //     config.override_queries = Some(|_, providers| {
     //         providers.extern_queries.used_crate_source = |tcx, cnum| {
@@ -63,101 +149,495 @@ impl Callbacks for LCallback {
         _compiler: &Compiler,
         tcx: TyCtxt<'tcx>,
     ) -> Compilation {
-        for local_def_id in tcx.hir_body_owners() { 
-            // https://doc.rust-lang.org/beta/nightly-rustc/rustc_hir/def/enum.DefKind.html 
-            // I don't think other types of DefKinds are relevant to us. 
-            if matches!(tcx.def_kind(local_def_id), DefKind::Fn | DefKind::AssocFn) {
-                let mir_body = tcx.optimized_mir(local_def_id);
-                trace_mir_body(tcx, mir_body);
+        let out_dir = output_dir(tcx, &self.args);
+        let summaries = Rc::new(
+            out_dir
+                .as_deref()
+                .map(|dir| load_summaries(tcx, dir))
+                .unwrap_or_default(),
+        );
+        let mut summary = CrateSummary::new(
+            tcx.crate_name(LOCAL_CRATE).to_string(),
+            tcx.stable_crate_id(LOCAL_CRATE).as_u64(),
+        );
+        let mut hits = Vec::new();
+        for (mir_body, instance) in analyzed_bodies(tcx, self.args.mono) {
+            match self.args.action {
+                Action::Trace | Action::Callback => {
+                    if self.report {
+                        hits.extend(trace_mir_body(tcx, mir_body, &self.args, &summaries, instance));
+                    }
+                }
+                Action::Blocks => print_basic_blocks(tcx, mir_body),
+                Action::Local => print_local_decls(tcx, mir_body),
+                Action::Query => print_mir(tcx, mir_body),
+            }
+        }
+        // Summaries are of the generic MIR. Only other crates use them, and they only see
+        // public items.
+        let summarize = matches!(self.args.action, Action::Trace | Action::Callback)
+            && out_dir.is_some()
+            && self.args.summarize();
+        if summarize {
+            for local_def_id in tcx.hir_body_owners() {
+                if matches!(tcx.def_kind(local_def_id), DefKind::Fn | DefKind::AssocFn)
+                    && tcx.effective_visibilities(()).is_exported(local_def_id)
+                {
+                    let mir_body = tcx.optimized_mir(local_def_id);
+                    let key = summary_key(tcx, local_def_id.to_def_id());
+                    let function = summarize_mir_body(tcx, mir_body, &self.args, &summaries);
+                    summary.functions.insert(key, function);
+                }
+            }
+        }
+        if matches!(self.args.action, Action::Trace | Action::Callback) {
+            if self.report {
+                report_hits(tcx, hits, &self.args, out_dir.as_deref());
+            }
+            if summarize
+                && let Some(dir) = &out_dir
+                && let Err(e) = write_summary(dir, &summary)
+            {
+                tcx.dcx().warn(format!(
+                    "could not write the LHS summary to {}: {e}",
+                    dir.display()
+                ));
+            }
+        }
+        if self.stop_after_analysis {
+            Compilation::Stop
+        } else {
+            Compilation::Continue
+        }
+    }
+}
+
+/// The MIR of a body owner we analyze: functions, closures and coroutines (the bodies of
+/// `async fn`s and blocks) have optimized MIR, constants and statics the MIR that const
+/// evaluation runs. Anonymous constants (array lengths and the like) are left out.
+pub fn owner_body(tcx: TyCtxt<'_>, local_def_id: LocalDefId) -> Option<&Body<'_>> {
+    // https://doc.rust-lang.org/beta/nightly-rustc/rustc_hir/def/enum.DefKind.html
+    match tcx.def_kind(local_def_id) {
+        DefKind::Fn | DefKind::AssocFn | DefKind::Closure | DefKind::SyntheticCoroutineBody => {
+            Some(tcx.optimized_mir(local_def_id))
+        }
+        DefKind::Const | DefKind::AssocConst | DefKind::Static { .. } => {
+            Some(tcx.mir_for_ctfe(local_def_id))
+        }
+        _ => None,
+    }
+}
+
+/// The bodies to analyze, and whether each is an instance. With `--mono` those are the
+/// instances of the crate's functions and closures that get codegened, plus the generic MIR
+/// of the other body owners; otherwise the generic MIR of every body owner.
+pub fn analyzed_bodies<'tcx>(tcx: TyCtxt<'tcx>, mono: bool) -> Vec<(&'tcx Body<'tcx>, bool)> {
+    let is_fn = |def_id| {
+        matches!(
+            tcx.def_kind(def_id),
+            DefKind::Fn | DefKind::AssocFn | DefKind::Closure | DefKind::SyntheticCoroutineBody
+        )
+    };
+    let mut bodies = Vec::new();
+    let mut instantiated = HashSet::new();
+    if mono {
+        let mut seen = HashSet::new();
+        for cgu in tcx.collect_and_partition_mono_items(()).codegen_units {
+            for item in cgu.items().keys() {
+                if let MonoItem::Fn(instance) = item
+                    && let InstanceKind::Item(def_id) = instance.def
+                    && def_id.is_local()
+                    && is_fn(def_id)
+                    && seen.insert(*instance)
+                {
+                    instantiated.insert(def_id);
+                    bodies.push((instance_body(tcx, *instance), true));
+                }
             }
         }
-        Compilation::Continue
     }
+    for local_def_id in tcx.hir_body_owners() {
+        if !instantiated.contains(&local_def_id.to_def_id())
+            && let Some(body) = owner_body(tcx, local_def_id)
+        {
+            bodies.push((body, false));
+        }
+    }
+    bodies
 }
 
-pub fn trace_mir_body<'tcx>(tcx: TyCtxt<'tcx>, mir_body: &'tcx Body<'tcx>) {
+pub fn trace_mir_body<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    mir_body: &'tcx Body<'tcx>,
+    args: &Args,
+    summaries: &Rc<Summaries>,
+    instance: bool,
+) -> Vec<SinkHit> {
     // dbg!("{}", &mir_body);
     let cfg = z3::Config::new();
     let ctx = z3::Context::new(&cfg);
     let mut ev = SymExec::new(&ctx);
 
-    for (local, local_decl) in mir_body.local_decls.iter_enumerated() {
-        match local_decl.ty.kind() {
-            TyKind::Int(_) => ev.create_int(local.as_usize().to_string().as_str()),
-            TyKind::Uint(_) => ev.create_int(local.as_usize().to_string().as_str()),
-            TyKind::Str => ev.create_uninterpreted_string(local.as_usize().to_string().as_str()),
-            TyKind::Char => ev.create_uninterpreted_string(local.as_usize().to_string().as_str()),
-            TyKind::Ref(_, ty, _) => {
-                if ty.is_str() {
-                    ev.create_uninterpreted_string(local.as_usize().to_string().as_str())
-                }
-            }
-            TyKind::Bool => ev.create_uninterpreted_bool(local.as_usize().to_string().as_str()),
-            TyKind::Adt(fields, _) => {
-                let def_ids = fields
-                    .all_fields()
-                    .map(|field| field.did)
-                    .collect::<Vec<_>>();
-
-                // Now we have a Vec containing all DefIds
-                for def_id in &def_ids {
-                    if def_id.index.as_usize() == DEF_ID_PATH_BUF {
-                        ev.create_uninterpreted_string(local.as_usize().to_string().as_str());
-                        break;
-                    }
-                }
-            }
-            // _ => println!("Unsupported Type: {}", local_decl.ty),
-            _ => {
-                // println!("Unsupported Type: {}", local_decl.ty);
-            }
-        }
+    declare_locals(&mut ev, mir_body, args.numeric);
+    if params_are_sources(tcx, mir_body.source.def_id()) {
+        taint_params(tcx, mir_body, &mut ev);
     }
 
     // let mut mir_parser = MIRParser::new(mir_body, ev);
     let mut mir_parser = MIRParser::new(tcx, mir_body, ev);
+    mir_parser.numeric = args.numeric;
+    mir_parser.bit_precise = args.bit_precise;
+    mir_parser.trace = args.action == Action::Trace;
+    mir_parser.summaries = summaries.clone();
+    mir_parser.mono = instance;
     // let fs_write_span: Option<rustc_span::Span> = mir_parser.parse();
-    let dangerous_spans: HashMap<(String, String), Vec<Span>> = mir_parser.parse();
-    println!("=== Dangerous Spans ===");
-    if dangerous_spans.is_empty() {
-        println!("No dangerous spans found.");
+    mir_parser.parse()
+}
+
+/// Whether the parameters of `def_id` are tainted, under the `tainted_params` settings
+fn params_are_sources(tcx: TyCtxt<'_>, def_id: DefId) -> bool {
+    let policy = &settings().tainted_params;
+    let Some(local_def_id) = def_id.as_local() else {
+        return false;
+    };
+    if !matches!(tcx.def_kind(def_id), DefKind::Fn | DefKind::AssocFn) {
+        return false;
+    }
+    (policy.main && tcx.entry_fn(()).is_some_and(|(entry, _)| entry == def_id))
+        || (policy.public && tcx.effective_visibilities(()).is_exported(local_def_id))
+        || policy
+            .functions
+            .iter()
+            .any(|pattern| path_matches(pattern, &tcx.def_path_str(def_id)))
+}
+
+// Taints every parameter of `mir_body` and what a reference parameter points to, with the
+// parameter as the source of the flow
+fn taint_params<'tcx>(tcx: TyCtxt<'tcx>, mir_body: &Body<'tcx>, ev: &mut SymExec<'_>) {
+    let path = tcx.def_path_str(mir_body.source.def_id());
+    for local in mir_body.args_iter() {
+        let name = mir_body
+            .var_debug_info
+            .iter()
+            .find(|info| matches!(info.value, VarDebugInfoContents::Place(p) if p.local == local))
+            .map(|info| format!("`{}`", info.name))
+            .unwrap_or_else(|| local.as_usize().to_string());
+        let span = mir_body.local_decls[local].source_info.span;
+        let message = format!("tainted data comes from parameter {name} of `{path}`");
+        let key = local.as_usize().to_string();
+        ev.taint_with_step(&key, span, message.clone(), None);
+        ev.taint_with_step(&format!("{key}*"), span, message, None);
+    }
+}
+
+/// Summarizes what a call to `mir_body` does with the taint of its arguments. The body is
+/// analyzed once with no tainted argument, then once for each argument with only that one
+/// tainted; what changes between the two is the argument's doing.
+pub fn summarize_mir_body<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    mir_body: &'tcx Body<'tcx>,
+    args: &Args,
+    summaries: &Rc<Summaries>,
+) -> FunctionSummary {
+    let run = |tainted_arg: Option<usize>| {
+        let cfg = z3::Config::new();
+        let ctx = z3::Context::new(&cfg);
+        let mut ev = SymExec::new(&ctx);
+        declare_locals(&mut ev, mir_body, args.numeric);
+        if let Some(arg) = tainted_arg {
+            let local = (arg + 1).to_string();
+            ev.set_taint(&local, true);
+            ev.set_taint(&format!("{local}*"), true);
+        }
+        let mut mir_parser = MIRParser::new(tcx, mir_body, ev);
+        mir_parser.numeric = args.numeric;
+        mir_parser.bit_precise = args.bit_precise;
+        mir_parser.collect_returns = true;
+        mir_parser.summaries = summaries.clone();
+        let hits = mir_parser.parse();
+        let returns_tainted = mir_parser.returned.iter().any(|s| s.is_tainted("0"));
+        (hits, returns_tainted)
+    };
+
+    let (base_hits, returns_tainted) = run(None);
+    let mut summary = FunctionSummary {
+        path: tcx.def_path_str(mir_body.source.def_id()),
+        returns_tainted,
+        ..FunctionSummary::default()
+    };
+    let same_call = |a: &SinkHit, b: &SinkHit| {
+        a.span == b.span && a.finding.sink == b.finding.sink && a.finding.forbidden == b.finding.forbidden
+    };
+    for arg in 0..mir_body.arg_count {
+        let (hits, returns_tainted) = run(Some(arg));
+        for hit in hits.iter().filter(|h| h.finding.tainted && !annotations(tcx).allows(h)) {
+            if base_hits.iter().any(|b| b.finding.tainted && same_call(b, hit)) {
+                continue;
+            }
+            let arg_sink = ArgSink {
+                arg,
+                sink: hit.finding.sink.clone(),
+                sink_arg: hit.finding.arg_idx,
+                forbidden: hit.finding.forbidden.clone(),
+                location: hit.finding.location.clone(),
+            };
+            if !summary.sinks.contains(&arg_sink) {
+                summary.sinks.push(arg_sink);
+            }
+        }
+        if returns_tainted && !summary.returns_tainted {
+            summary.tainted_return_from.push(arg);
+        }
+    }
+    summary
+}
+
+/// The summaries the dependencies of the crate wrote to `out_dir`
+fn load_summaries(tcx: TyCtxt<'_>, out_dir: &Path) -> Summaries {
+    let mut summaries = Summaries::new();
+    for &cnum in tcx.crates(()) {
+        let crate_name = tcx.crate_name(cnum).to_string();
+        if let Some(summary) = read_summary(out_dir, &crate_name, tcx.stable_crate_id(cnum).as_u64())
+        {
+            summaries.extend(summary.functions);
+        }
+    }
+    summaries
+}
+
+/// Where the crate's report and summary go: `--out-dir`, or `lhs` in rustc's output directory
+fn output_dir(tcx: TyCtxt<'_>, args: &Args) -> Option<PathBuf> {
+    args.out_dir.clone().or_else(|| {
+        let io = &tcx.sess.io;
+        io.output_dir.as_ref().map(|dir| dir.join("lhs"))
+    })
+}
+
+/// Reports the hits of every analyzed body in the crate: as diagnostics, and as the crate's
+/// report in the output directory
+pub fn report_hits(tcx: TyCtxt<'_>, all_hits: Vec<SinkHit>, args: &Args, out_dir: Option<&Path>) {
+    // Instances of one generic function (`--mono`) hit the same calls
+    let mut hits = Vec::new();
+    for hit in all_hits {
+        if args.sink_level(&hit.finding.sink) != Level::Allow && !annotations(tcx).allows(&hit) {
+            merge_hit(&mut hits, hit);
+        }
+    }
+    // after merging, a call reached on a tainted path and on a clean one has the stronger tier
+    let tiers = if args.tiers.is_empty() {
+        &settings().tiers
+    } else {
+        &args.tiers
+    };
+    hits.retain(|hit| tiers.contains(&hit.finding.tier));
+    // A sink in a helper is also reached through every caller of the helper, those hits only
+    // stay when they say more than the one of a shorter call chain (e.g. a tainted argument)
+    let all = hits.clone();
+    hits.retain(|hit| {
+        !all.iter().any(|other| {
+            other.span == hit.span
+                && other.finding.sink == hit.finding.sink
+                && other.finding.forbidden == hit.finding.forbidden
+                && other.finding.call_chain.len() < hit.finding.call_chain.len()
+                && other.finding.tier <= hit.finding.tier
+        })
+    });
+    emit_diagnostics(tcx, &hits, args);
+
+    let Some(out_dir) = out_dir else {
+        return;
+    };
+    let crate_name = tcx.crate_name(LOCAL_CRATE).to_string();
+    let kind = if tcx.sess.opts.test {
+        "test".to_string()
     } else {
-        let sm = tcx.sess.source_map();
-        for ((func, arg), spans) in &dangerous_spans {
-            println!("=== {} (arg = {:?}) ===", func, arg);
-            for (i, sp) in spans.iter().enumerate() {
-                let loc = sm.span_to_string(*sp, FileNameDisplayPreference::Local); // or span_to_filename_string if you prefer
-                println!("  [{:02}] {}", i + 1, loc);
+        let kinds: Vec<String> = tcx.crate_types().iter().map(|t| t.to_string()).collect();
+        kinds.join("+")
+    };
+    let hash = tcx.stable_crate_id(LOCAL_CRATE).as_u64();
+    let name = report_file_name(&crate_name, &kind, hash);
+    let report = Report::new(crate_name, hits.into_iter().map(|h| h.finding).collect());
+    if let Err(e) = write_report(out_dir, &name, &report) {
+        tcx.dcx().warn(format!(
+            "could not write the LHS report to {}: {e}",
+            out_dir.display()
+        ));
+    }
+}
+
+/// Reports every sink hit as a compiler diagnostic, so cargo shows it inline with the other
+/// diagnostics, deduplicates it, and includes it in `--message-format=json`. Hits of denied
+/// sinks are errors, which makes rustc (and with it the build) fail.
+pub fn emit_diagnostics(tcx: TyCtxt<'_>, hits: &[SinkHit], args: &Args) {
+    for SinkHit {
+        span,
+        finding,
+        call_spans,
+        flow_spans,
+    } in hits
+    {
+        let (sink, forbidden) = (&finding.sink, &finding.forbidden);
+        let diag_level = match args.sink_level(sink) {
+            Level::Allow => continue,
+            Level::Warn => rustc_errors::Level::Warning,
+            Level::Deny => rustc_errors::Level::Error,
+        };
+        let label = match finding.tier {
+            Tier::Definite => format!("this argument is always `{forbidden}`"),
+            Tier::TaintedPossible => format!("tainted data that can be `{forbidden}` reaches this call"),
+            Tier::UntaintedPossible => format!("this argument can be `{forbidden}`"),
+            Tier::ImplicitFlowOnly => {
+                format!("this argument can be `{forbidden}` on a path chosen by tainted data")
             }
+        };
+        let msg = format!("call to `{sink}` may receive `{forbidden}`");
+        let mut diag = Diag::<()>::new(tcx.dcx(), diag_level, msg)
+            .with_span(*span)
+            .with_span_label(*span, label);
+        for (step, step_span) in finding.flow.iter().zip(flow_spans) {
+            diag = diag.with_span_note(*step_span, step.message.clone());
         }
-        dump_danger_csv(
-            tcx.sess.source_map(),
-            &dangerous_spans,
-            "dangerous_spans.csv",
-        );
+        for (i, step) in finding.call_chain.iter().enumerate() {
+            diag = match call_spans.get(i) {
+                Some(call_span) => {
+                    diag.with_span_note(*call_span, format!("reached through this {}", step.message))
+                }
+                // a step in another crate, known from its summary
+                None => diag.with_note(format!("reached through the {} at {}", step.message, step.location)),
+            };
+        }
+        if !finding.witness.is_empty() {
+            let values: Vec<String> = finding
+                .witness
+                .iter()
+                .map(|w| format!("{} = {}", w.input, w.value))
+                .collect();
+            diag = diag.with_note(format!("for example with {}", values.join(", ")));
+        }
+        diag.with_note(format!("`{sink}` is a sink and `{forbidden}` is forbidden for it"))
+            .with_note(format!("finding tier: {}", finding.tier))
+            .with_help(format!(
+                "check that the argument cannot be `{forbidden}` before calling `{sink}`"
+            ))
+            .emit();
     }
 }
 
-pub fn dump_danger_csv(sm: &SourceMap, map: &HashMap<(String, String), Vec<Span>>, path: &str) {
-    let path = Path::new(path);
+pub fn print_basic_blocks<'tcx>(tcx: TyCtxt<'tcx>, mir_body: &'tcx Body<'tcx>) {
+    println!(
+        "MIR for function: {}",
+        tcx.def_path_str(mir_body.source.def_id())
+    );
+    for (bb, data) in mir_body.basic_blocks.iter_enumerated() {
+        println!("{bb:?}: {} statements", data.statements.len());
+        for stmt in &data.statements {
+            println!("    {stmt:?}");
+        }
+        println!("    {:?}", data.terminator().kind);
+    }
+}
 
-    // Check if the file already exists
-    let file_exists = path.exists();
+pub fn print_local_decls<'tcx>(tcx: TyCtxt<'tcx>, mir_body: &'tcx Body<'tcx>) {
+    println!(
+        "MIR for function: {}",
+        tcx.def_path_str(mir_body.source.def_id())
+    );
+    for (local, local_decl) in mir_body.local_decls.iter_enumerated() {
+        println!("    {local:?}: {}", local_decl.ty);
+    }
+}
 
-    // Open the file in append mode
-    if let Ok(file) = OpenOptions::new().create(true).append(true).open(path) {
-        let mut writer = BufWriter::new(file);
+pub fn print_mir<'tcx>(tcx: TyCtxt<'tcx>, mir_body: &'tcx Body<'tcx>) {
+    let mut out = std::io::stdout().lock();
+    let options = PrettyPrintMirOptions::from_cli(tcx);
+    let _ = write_mir_fn(tcx, mir_body, &mut |_, _| Ok(()), &mut out, options);
+}
 
-        // Only write header if file didn't exist before
-        if !file_exists {
-            let _ = writeln!(writer, "function,value,index,span");
+/// Compiles `source` as a library, with the further `rustc_args`, and hands its type context to
+/// `f`, for tests that need MIR
+#[cfg(test)]
+pub(crate) fn with_tcx(
+    name: &str,
+    source: &str,
+    rustc_args: &[&str],
+    f: impl FnOnce(TyCtxt<'_>) + Send,
+) {
+    struct Analyze<F>(Option<F>);
+    impl<F: FnOnce(TyCtxt<'_>) + Send> Callbacks for Analyze<F> {
+        fn after_analysis<'tcx>(
+            &mut self,
+            _compiler: &Compiler,
+            tcx: TyCtxt<'tcx>,
+        ) -> Compilation {
+            (self.0.take().unwrap())(tcx);
+            Compilation::Stop
         }
+    }
+    compile_source(name, source, rustc_args, &mut Analyze(Some(f)));
+}
 
-        for ((func, arg), spans) in map {
-            for (i, sp) in spans.iter().enumerate() {
-                let span_str = sm.span_to_string(*sp, FileNameDisplayPreference::Local);
-                let _ = writeln!(writer, "{},{},{},{}", func, arg, i + 1, span_str);
-            }
+/// Compiles `source` as a library with `callbacks` and the further `rustc_args`, and returns
+/// whether that succeeded
+#[cfg(test)]
+fn compile_source(
+    name: &str,
+    source: &str,
+    rustc_args: &[&str],
+    callbacks: &mut (dyn Callbacks + Send),
+) -> bool {
+    let dir = std::env::temp_dir().join(format!("lhs-{name}-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join(format!("{name}.rs"));
+    std::fs::write(&file, source).unwrap();
+    let rustc_args = [
+        "rustc",
+        file.to_str().unwrap(),
+        "--crate-type=lib",
+        "--edition=2024",
+        "--out-dir",
+        dir.to_str().unwrap(),
+    ]
+    .iter()
+    .chain(rustc_args)
+    .map(|arg| arg.to_string())
+    .collect::<Vec<_>>();
+    let exit_code =
+        rustc_driver::catch_with_exit_code(|| rustc_driver::run_compiler(&rustc_args, callbacks));
+    let _ = std::fs::remove_dir_all(&dir);
+    exit_code == 0
+}
+
+/// Analyzes `source` with `args` and returns the findings of all its bodies, for tests
+#[cfg(test)]
+pub(crate) fn analyze_source(
+    name: &str,
+    source: &str,
+    args: Args,
+) -> Vec<crate::report::Finding> {
+    let mut findings = Vec::new();
+    with_tcx(name, source, &[], |tcx| {
+        for (mir_body, instance) in analyzed_bodies(tcx, args.mono) {
+            let hits = trace_mir_body(tcx, mir_body, &args, &Rc::default(), instance);
+            findings.extend(hits.into_iter().map(|hit| hit.finding));
         }
+    });
+    findings
+}
+
+#[test]
+fn test_register_tool() {
+    // The annotations compile whether the crate registers the tool or leaves it to us
+    let annotated = "#[cfg_attr(lhs, lhs::source)]\npub fn read() -> String { String::new() }\n";
+    let roots = [
+        "",
+        "#![feature(register_tool)]\n#![register_tool(lhs)]\n",
+        "#![cfg_attr(lhs, feature(register_tool), register_tool(lhs))]\n",
+        "#![feature(register_tool, never_type)]\n#![register_tool(clippy_ext, lhs)]\n",
+    ];
+    for (i, root) in roots.iter().enumerate() {
+        let name = format!("register_tool_{i}");
+        let source = format!("{root}{annotated}");
+        let mut callbacks = LCallback::standalone(Args::default());
+        assert!(compile_source(&name, &source, &[], &mut callbacks), "{root}");
     }
 }
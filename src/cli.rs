@@ -0,0 +1,250 @@
+//!
+//! Command line options for the `lhs` driver
+//!
+//! The same binary is used as a `RUSTC_WRAPPER` (cargo calls it as `lhs <rustc> <args>...`)
+//! and as a tool we run by hand. LHS flags therefore come from one of two places:
+//! - everything before a `--` separator: `lhs --action blocks -- rustc src/main.rs`
+//! - the `LHS_ARGS` environment variable when there is no separator, which is how cargo
+//!   runs us: `LHS_ARGS="--action trace" RUSTC_WRAPPER=lhs cargo build`
+//!
+use clap::{Parser, ValueEnum};
+use std::path::PathBuf;
+
+use crate::report::Tier;
+
+/// Environment variable holding the LHS flags when running as a rustc wrapper
+pub const LHS_ARGS_ENV: &str = "LHS_ARGS";
+
+#[derive(Parser, Debug, Clone)]
+#[command(name = "LHS: Leveraging (not) HIR via Symbolic execution")]
+#[command(version = "1.0.1")]
+#[command(about = "A command line utility for searching for analyzing a given Rust code's MIR and verifying proc/self/mem safety", long_about = None)]
+pub struct Args {
+    /// Filepath of Rust code
+    #[arg(short, long)]
+    pub source: Option<String>,
+
+    /// Tool action options
+    #[arg(short, long, value_enum, default_value_t = Action::Callback)]
+    pub action: Action,
+
+    /// Toggle matching with numeric variables
+    #[arg(long)]
+    pub numeric: bool,
+
+    /// Model integers with the width and signedness of their type: arithmetic wraps around,
+    /// overflow checks can fail, and bitwise operations, shifts and casts are exact. Implies
+    /// `--numeric`.
+    #[arg(long)]
+    pub bit_precise: bool,
+
+    /// Analyze functions at their concrete instantiations, resolving trait method calls to the
+    /// impl that is called
+    #[arg(long)]
+    pub mono: bool,
+
+    /// Directory each analyzed crate writes its JSON report to, one file per crate and target
+    /// (default: `lhs` in rustc's output directory, no report when there is none)
+    #[arg(long, env = "LHS_OUT_DIR")]
+    pub out_dir: Option<PathBuf>,
+
+    /// Combine the reports in this directory, print them in `--format` and exit
+    #[arg(long, value_name = "DIR")]
+    pub merge: Option<PathBuf>,
+
+    /// Format `--merge` prints the combined reports in
+    #[arg(long, value_enum, default_value_t = Format::Json)]
+    pub format: Format,
+
+    /// Also analyze every dependency, not only the packages cargo was asked to build
+    #[arg(long)]
+    pub include_deps: bool,
+
+    /// Dependencies to analyze along with the primary packages (comma separated)
+    #[arg(long, value_delimiter = ',')]
+    pub deps: Vec<String>,
+
+    /// Write a summary of every dependency that is not analyzed, and of every analyzed crate,
+    /// so that the analyzed crates can follow calls into them
+    #[arg(long)]
+    pub summarize_deps: bool,
+
+    /// Settings file (default: `LHS_CONFIG`, or the closest `lhs.toml` above the project)
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Do not report hits of this sink (a configured sink path, or `all`)
+    #[arg(short = 'A', long = "allow", value_name = "SINK")]
+    pub allow: Vec<String>,
+
+    /// Report hits of this sink as warnings, the default (a sink path, or `all`)
+    #[arg(short = 'W', long = "warn", value_name = "SINK")]
+    pub warn: Vec<String>,
+
+    /// Report hits of this sink as errors and fail the compilation (a sink path, or `all`)
+    #[arg(short = 'D', long = "deny", value_name = "SINK")]
+    pub deny: Vec<String>,
+
+    /// Report only findings of these tiers (comma separated): definite, tainted-possible,
+    /// untainted-possible, implicit-flow-only (default: `tiers` in the settings)
+    #[arg(long = "tier", value_name = "TIER", value_delimiter = ',')]
+    pub tiers: Vec<Tier>,
+}
+
+impl Args {
+    /// Whether the crate rustc is about to compile should be analyzed. Cargo marks the
+    /// packages it was asked to build with `CARGO_PRIMARY_PACKAGE`; everything else is a
+    /// dependency and only analyzed on request. Outside of cargo, we always analyze.
+    pub fn in_scope(&self) -> bool {
+        let Ok(crate_name) = std::env::var("CARGO_CRATE_NAME") else {
+            return true;
+        };
+        if self.include_deps || std::env::var_os("CARGO_PRIMARY_PACKAGE").is_some() {
+            return true;
+        }
+        let package = std::env::var("CARGO_PKG_NAME").unwrap_or_default();
+        self.deps
+            .iter()
+            .any(|dep| dep.replace('-', "_") == crate_name || *dep == package)
+    }
+
+    /// Whether the crate rustc is about to compile should write its summary. Only the crates
+    /// compiled after it read summaries, so that is a dependency, analyzed or summarized, or
+    /// any crate once `--summarize-deps` asks for them.
+    pub fn summarize(&self) -> bool {
+        self.summarize_deps
+            || std::env::var_os("CARGO_CRATE_NAME").is_some()
+                && std::env::var_os("CARGO_PRIMARY_PACKAGE").is_none()
+    }
+}
+
+impl Args {
+    /// The level hits of `sink` are reported at. A flag naming the sink wins over `all`, and
+    /// when several flags name it the strictest one wins.
+    pub fn sink_level(&self, sink: &str) -> Level {
+        let named = |names: &[String], name: &str| names.iter().any(|n| n == name);
+        let level_of = |name: &str| {
+            if named(&self.deny, name) {
+                Some(Level::Deny)
+            } else if named(&self.warn, name) {
+                Some(Level::Warn)
+            } else if named(&self.allow, name) {
+                Some(Level::Allow)
+            } else {
+                None
+            }
+        };
+        level_of(sink)
+            .or_else(|| level_of("all"))
+            .unwrap_or(Level::Warn)
+    }
+
+    /// Every sink named by `--allow`/`--warn`/`--deny`, except `all`
+    pub fn named_sinks(&self) -> impl Iterator<Item = &str> {
+        self.allow
+            .iter()
+            .chain(&self.warn)
+            .chain(&self.deny)
+            .map(String::as_str)
+            .filter(|n| *n != "all")
+    }
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Args::parse_from(["lhs"])
+    }
+}
+
+#[derive(Debug, ValueEnum, Clone, Copy, Eq, PartialEq)]
+pub enum Action {
+    /// Run the analysis and print the basic blocks visited on every path
+    Trace,
+    /// Print the basic blocks of every analyzed body
+    Blocks,
+    /// Print the local declarations of every analyzed body
+    Local,
+    /// Print the optimized MIR of every analyzed body
+    Query,
+    /// Run the analysis from the compiler callback and report findings
+    Callback,
+}
+
+#[derive(Debug, ValueEnum, Clone, Copy, Eq, PartialEq)]
+pub enum Format {
+    /// `function,value,index,span` rows
+    Csv,
+    /// JSON Lines, one `report::Report` per crate
+    Json,
+    /// A SARIF 2.1.0 log
+    Sarif,
+}
+
+/// How a sink hit is reported
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+/// Splits the driver's argv (without the program name) into LHS options and the rustc
+/// command line. The returned rustc command line starts with the rustc binary, as cargo
+/// passes it to a wrapper, and is empty when LHS was run by hand without one.
+pub fn parse_args(argv: Vec<String>) -> (Args, Vec<String>) {
+    let (lhs_args, rustc_args) = split_args(argv, std::env::var(LHS_ARGS_ENV).ok());
+    let mut args = Args::parse_from(std::iter::once("lhs".to_string()).chain(lhs_args));
+    args.numeric |= args.bit_precise;
+    (args, rustc_args)
+}
+
+fn split_args(mut argv: Vec<String>, env_args: Option<String>) -> (Vec<String>, Vec<String>) {
+    if let Some(sep) = argv.iter().position(|a| a == "--") {
+        let rustc_args = argv.split_off(sep + 1);
+        argv.pop();
+        return (argv, rustc_args);
+    }
+    // A leading flag means we were run by hand with no rustc command line at all
+    if argv.first().is_some_and(|a| a.starts_with('-')) {
+        return (argv, Vec::new());
+    }
+    let lhs_args = env_args
+        .map(|s| s.split_whitespace().map(String::from).collect())
+        .unwrap_or_default();
+    (lhs_args, argv)
+}
+
+#[test]
+fn test_split_args() {
+    let argv = |s: &str| s.split_whitespace().map(String::from).collect::<Vec<_>>();
+
+    let (lhs, rustc) = split_args(argv("--action blocks -- rustc src/main.rs"), None);
+    assert_eq!(lhs, argv("--action blocks"));
+    assert_eq!(rustc, argv("rustc src/main.rs"));
+
+    let (lhs, rustc) = split_args(argv("rustc --crate-name foo"), Some("--numeric".into()));
+    assert_eq!(lhs, argv("--numeric"));
+    assert_eq!(rustc, argv("rustc --crate-name foo"));
+
+    let (lhs, rustc) = split_args(argv("--source ex1.rs"), Some("--action trace".into()));
+    assert_eq!(lhs, argv("--source ex1.rs"));
+    assert!(rustc.is_empty());
+}
+
+#[test]
+fn test_sink_level() {
+    let args = Args::parse_from(["lhs", "-D", "all", "-W", "std::env::set_var"]);
+    assert_eq!(args.sink_level("std::fs::write"), Level::Deny);
+    assert_eq!(args.sink_level("std::env::set_var"), Level::Warn);
+
+    let args = Args::parse_from(["lhs", "-A", "std::fs::write", "-D", "std::fs::write"]);
+    assert_eq!(args.sink_level("std::fs::write"), Level::Deny);
+    assert_eq!(args.sink_level("std::env::set_var"), Level::Warn);
+}
+
+#[test]
+fn test_tiers() {
+    let args = Args::parse_from(["lhs", "--tier", "definite,implicit-flow-only"]);
+    assert_eq!(args.tiers, [Tier::Definite, Tier::ImplicitFlowOnly]);
+    assert!(Args::try_parse_from(["lhs", "--tier", "possible"]).is_err());
+}
@@ -4,7 +4,9 @@ use rustc_middle::{
 };
 use z3::SatResult;
 
-use crate::parser::{Call, MIRParser};
+use crate::parser::{is_byte_buffer, ByteView, Call, MIRParser};
+use crate::report::MatchKind;
+use crate::symexec::BytesPart;
 
 // Hassnain : Removed these function, as we are using a generic string matching fucniton now
 // pub(crate) fn handle_fs_write<'tcx, 'mir, 'ctx>(this: &mut MIRParser<'tcx, 'mir, 'ctx>, call: Call<'tcx>) {
@@ -56,9 +58,7 @@ pub(crate) fn handle_string_from<'tcx, 'mir, 'ctx>(
         this.curr.assign_string(&key, s);
 
         // If the argument was tainted, the new String is tainted, too.
-        if this.operand_tainted(&call.args[0]) {
-            this.curr.set_taint(&key, true);
-        }
+        this.taint_through_call(&call, &call.args[0], &key);
     }
 }
 
@@ -101,9 +101,7 @@ pub(crate) fn handle_from_trait<'tcx, 'mir, 'ctx>(
         this.curr.assign_string(&key, val);
 
         // propagate taint from the arg to the dest
-        if this.operand_tainted(&call.args[0]) {
-            this.curr.set_taint(&key, true);
-        }
+        this.taint_through_call(&call, &call.args[0], &key);
     }
 }
 
@@ -111,7 +109,6 @@ pub(crate) fn generic_string_handler<'tcx, 'mir, 'ctx>(
     this: &mut MIRParser<'tcx, 'mir, 'ctx>,
     call: Call<'tcx>,
 ) {
-    dbg!();
     // Which arg to look at (defaults to 0 if no SinkInformation)
     let idx = call.sink.map(|s| s.arg_idx).unwrap_or(0);
     let Some(arg) = call.args.get(idx) else {
@@ -124,14 +121,13 @@ pub(crate) fn generic_string_handler<'tcx, 'mir, 'ctx>(
         this.curr.assign_string(&dest_key, sym_str.clone());
 
         // propagate taint from the arg to the dest
-        if this.operand_tainted(arg) {
-            this.curr.set_taint(&dest_key, true);
-        }
+        this.taint_through_call(&call, arg, &dest_key);
 
         if let Some(info) = call.sink {
             let s: &z3::ast::String<'ctx> = &sym_str;
             // let dest_expr = this.curr.get_string(&dest_key).unwrap();
-            let use_regex = info.forbidden_val.contains('*');
+            // an absolute path is checked against the ways of writing it (see `resolves_to`)
+            let use_regex = info.forbidden_val.contains('*') || info.forbidden_val.starts_with('/');
 
             let (could_match, always_match) = if use_regex {
                 (
@@ -147,23 +143,25 @@ pub(crate) fn generic_string_handler<'tcx, 'mir, 'ctx>(
                 )
             };
 
-            // Is the argument source tainted?
-            let tainted = this.operand_tainted(arg);
-
             /*
-            Report in two cases
-            // Case 1
-            i) Value is tainted
-            ii) Value may have forbidden value in some executions.
-            // Case 2
+            Report whenever the value may be forbidden, the tier of the hit (see `Tier`) says
+            how strong the evidence is:
+            // definite
             i) Value will be forbidden in ALL execution (handle consts)
+            // tainted-possible, implicit-flow-only, untainted-possible
+            i) Value may have forbidden value in some executions.
+            ii) Value is tainted, the path is tainted, or neither.
             */
-            dbg!(could_match, always_match, tainted);
-            if (could_match && tainted) || always_match {
-                if let Some(span) = call.span {
-                    let func_path = this.def_path_str(call.func_def_id);
-                    this.record_sink_hit(&func_path, info.forbidden_val, span);
-                }
+            if (could_match || always_match)
+                && let Some(span) = call.span
+            {
+                let func_path = this.def_path_str(call.func_def_id);
+                let match_kind = if always_match {
+                    MatchKind::Always
+                } else {
+                    MatchKind::Possible
+                };
+                this.record_sink_hit(&func_path, info, span, match_kind, arg, s);
             }
         }
     }
@@ -178,7 +176,23 @@ pub(crate) fn handle_generic_source<'tcx, 'mir, 'ctx>(
     call: Call<'tcx>,
 ) {
     let key = this.place_key(&call.dest);
-    this.curr.set_taint(&key, true);
+    match call.span {
+        Some(span) => {
+            let path = this.def_path_str(call.func_def_id);
+            let message = format!("tainted data comes from this call to `{path}`");
+            this.curr.taint_with_step(&key, span, message, None);
+        }
+        None => this.curr.set_taint(&key, true),
+    }
+}
+
+/// A sanitizer returns data that is safe whatever its arguments are
+pub(crate) fn handle_sanitizer<'tcx, 'mir, 'ctx>(
+    this: &mut MIRParser<'tcx, 'mir, 'ctx>,
+    call: Call<'tcx>,
+) {
+    let key = this.place_key(&call.dest);
+    this.curr.set_taint(&key, false);
 }
 
 pub(crate) fn handle_pathbuf_push<'tcx, 'mir, 'ctx>(
@@ -203,11 +217,12 @@ pub(crate) fn handle_pathbuf_push<'tcx, 'mir, 'ctx>(
     if let (Some(base), Some(comp)) = (base_opt, comp_opt) {
         let joined = this.curr.path_join(&base, &comp);
         this.curr.assign_string(&pointee_key, joined);
-        if this.operand_tainted(&call.args[1]) || this.operand_tainted(&call.args[0]) {
-            this.curr.set_taint(&pointee_key, true);
-        }
-    } else if this.operand_tainted(&call.args[1]) || this.operand_tainted(&call.args[0]) {
-        this.curr.set_taint(&pointee_key, true);
+    }
+    // the pushed component first, so the flow leads to it rather than to the base
+    if this.operand_tainted(&call.args[1]) {
+        this.taint_through_call(&call, &call.args[1], &pointee_key);
+    } else {
+        this.taint_through_call(&call, &call.args[0], &pointee_key);
     }
 }
 
@@ -222,9 +237,7 @@ pub(crate) fn handle_path_new<'tcx, 'mir, 'ctx>(
     if let Some(s) = this.get_string_from_operand(&call.args[0]) {
         let key = this.place_key(&call.dest);
         this.curr.assign_string(&key, s);
-        if this.operand_tainted(&call.args[0]) {
-            this.curr.set_taint(&key, true);
-        }
+        this.taint_through_call(&call, &call.args[0], &key);
     }
 }
 
@@ -239,9 +252,7 @@ pub(crate) fn handle_path_to_path_buf<'tcx, 'mir, 'ctx>(
     if let Some(s) = this.get_string_from_operand(&call.args[0]) {
         let key = this.place_key(&call.dest);
         this.curr.assign_string(&key, s);
-        if this.operand_tainted(&call.args[0]) {
-            this.curr.set_taint(&key, true);
-        }
+        this.taint_through_call(&call, &call.args[0], &key);
     }
 }
 
@@ -256,8 +267,9 @@ pub(crate) fn handle_string_from_utf8_lossy<'tcx, 'mir, 'ctx>(
 
     let dest_key = this.place_key(&call.dest);
 
-    // If we can see the string from the arg, reuse it - otherwise make new
-    if let Some(s) = this.get_string_from_operand(&call.args[0]) {
+    // If we can see the bytes of the arg, decode them - otherwise make new
+    if let Some(bytes) = this.get_string_from_operand(&call.args[0]) {
+        let s = this.curr.decode_utf8(&bytes, true);
         this.curr.assign_string(&dest_key, s);
     } else {
         // dbg!("think Hassnain!!"); - leaving this comment here, since I find it funny
@@ -268,9 +280,7 @@ pub(crate) fn handle_string_from_utf8_lossy<'tcx, 'mir, 'ctx>(
     }
 
     // Propagate taint: &[u8] input taints the Cow<str> result.
-    if this.operand_tainted(&call.args[0]) {
-        this.curr.set_taint(&dest_key, true);
-    }
+    this.taint_through_call(&call, &call.args[0], &dest_key);
 }
 
 pub(crate) fn handle_read_into_buf<'tcx, 'mir, 'ctx>(
@@ -284,12 +294,28 @@ pub(crate) fn handle_read_into_buf<'tcx, 'mir, 'ctx>(
     }
     if let Operand::Copy(p) | Operand::Move(p) = &call.args[1] {
         let key = this.place_key(p);
-        let base = this.resolve_alias(&key);
+        let base = this.byte_buffer_base(&key);
+
+        // the bytes read are unknown, and no more than the buffer holds
+        let max_len = this
+            .get_string_from_operand(&call.args[1])
+            .and_then(|buf| this.curr.bytes_len(&buf));
+        let read = this.curr.fresh_bytes("read", max_len);
+        this.write_byte_buffer(&key, read);
 
         // mark both the handle and the underlying buffer as tainted
-        dbg!(&key, &base, "positive tainted now");
-        this.curr.set_taint(&key, true); // &mut [u8]
-        this.curr.set_taint(&base, true); // [u8; N] backing array
+        match call.span {
+            Some(span) => {
+                let path = this.def_path_str(call.func_def_id);
+                let message = format!("tainted data is read into the buffer by this call to `{path}`");
+                this.curr.taint_with_step(&key, span, message.clone(), None); // &mut [u8]
+                this.curr.taint_with_step(&base, span, message, None); // [u8; N] backing array
+            }
+            None => {
+                this.curr.set_taint(&key, true);
+                this.curr.set_taint(&base, true);
+            }
+        }
     }
 }
 
@@ -317,7 +343,7 @@ pub(crate) fn handle_fmt_format<'tcx, 'mir, 'ctx>(
     this: &mut MIRParser<'tcx, 'mir, 'ctx>,
     call: Call<'tcx>,
 ) {
-    println!("Format! encountered, false positive introduced");
+    // Format! encountered, false positive introduced
     // this should store the result of the formatting in actual hashmap
 }
 
@@ -329,24 +355,21 @@ pub(crate) fn handle_string_from_utf8<'tcx, 'mir, 'ctx>(
         return;
     }
     let dest_key = this.place_key(&call.dest);
-    dbg!(&call.args[0], &call.dest, &call.span);
-    this.curr.dump_taint();
-    dbg!();
-    // see if you can get a string from the argument, if not make a new one
-    let s = this.curr.get_or_fresh_string(&dest_key);
+    // decode the bytes of the argument if we know them, if not make a new string
+    let s = match this.get_string_from_operand(&call.args[0]) {
+        Some(bytes) => this.curr.decode_utf8(&bytes, false),
+        None => this.curr.get_or_fresh_string(&dest_key),
+    };
     this.curr.assign_string(&dest_key, s);
 
     // If the Vec<u8> came from the network, taint the Result
-    if this.operand_tainted(&call.args[0]) {
-        this.curr.set_taint(&dest_key, true);
-    }
+    this.taint_through_call(&call, &call.args[0], &dest_key);
 }
 
 pub(crate) fn handle_result_unwrap_or_default<'tcx, 'mir, 'ctx>(
     this: &mut MIRParser<'tcx, 'mir, 'ctx>,
     call: Call<'tcx>,
 ) {
-    dbg!();
     if call.args.is_empty() {
         return;
     }
@@ -358,9 +381,6 @@ pub(crate) fn handle_result_unwrap_or_default<'tcx, 'mir, 'ctx>(
 
     let dest_key = this.place_key(&call.dest);
 
-    dbg!(src_key.clone(), dest_key.clone());
-    this.curr.dump_taint();
-
     if let Some(s) = this.curr.get_string(&src_key).cloned() {
         // Reuse the symbolic string
         this.curr.assign_string(&dest_key, s);
@@ -369,11 +389,7 @@ pub(crate) fn handle_result_unwrap_or_default<'tcx, 'mir, 'ctx>(
         let s = this.curr.get_or_fresh_string(&dest_key);
         this.curr.assign_string(&dest_key, s);
     }
-    dbg!();
-    if this.operand_tainted(&call.args[0]) {
-        dbg!();
-        this.curr.set_taint(&dest_key, true);
-    }
+    this.taint_through_call(&call, &call.args[0], &dest_key);
 }
 
 pub(crate) fn handle_deref_mut<'tcx, 'mir, 'ctx>(
@@ -398,10 +414,8 @@ pub(crate) fn handle_deref_mut<'tcx, 'mir, 'ctx>(
     this.aliases.insert(dest_key.clone(), base.clone());
 
     // If Vec was tainted, the slice is tainted and keep vec tainted as well
-    if this.operand_tainted(&call.args[0]) {
-        this.curr.set_taint(&dest_key, true);
-        this.curr.set_taint(&base, true);
-    }
+    this.taint_through_call(&call, &call.args[0], &dest_key);
+    this.taint_through_call(&call, &call.args[0], &base);
 }
 
 pub(crate) fn handle_deref_generic<'tcx, 'mir, 'ctx>(
@@ -433,12 +447,18 @@ pub(crate) fn handle_deref_generic<'tcx, 'mir, 'ctx>(
         _ => false,
     };
 
-    if !(is_self_pathbuf && dest_is_ref_to_path) {
+    // or is it a `&str` or `&[u8]` out of a `String`, `Cow` or `Vec<u8>`?
+    let dest_is_text = match dest_ty.kind() {
+        TyKind::Ref(_, inner, _) => inner.is_str() || is_byte_buffer(this.tcx, *inner),
+        _ => false,
+    };
+
+    if !(dest_is_text || is_self_pathbuf && dest_is_ref_to_path) {
         // Not the case we care about (atleast for now)
         return;
     }
 
-    // Move alias + value + taint from PathBuf to &Path
+    // Move alias + value + taint from PathBuf to &Path (or to the &str, &[u8])
     let self_key = match &call.args[0] {
         Operand::Copy(p) | Operand::Move(p) => this.place_key(p),
         Operand::Constant(_) => return,
@@ -455,7 +475,254 @@ pub(crate) fn handle_deref_generic<'tcx, 'mir, 'ctx>(
     this.aliases.insert(dest_key.clone(), base.clone());
 
     // Propagate taint from PathBuf to &Path
-    if this.operand_tainted(&call.args[0]) {
-        this.curr.set_taint(&dest_key, true);
+    this.taint_through_call(&call, &call.args[0], &dest_key);
+}
+
+// Byte buffers (`[u8; N]`, `&[u8]`, `Vec<u8>`) hold strings of bytes, see `SymExec::static_bytes`
+
+// Whether `op` is a byte buffer, or a reference to one
+fn is_bytes_operand<'tcx>(this: &MIRParser<'tcx, '_, '_>, op: &Operand<'tcx>) -> bool {
+    is_byte_buffer(this.tcx, op.ty(this.mir_body, this.tcx))
+}
+
+// Whether `op` is text, a `str` or a `String`, behind any references
+fn is_text_operand<'tcx>(this: &MIRParser<'tcx, '_, '_>, op: &Operand<'tcx>) -> bool {
+    let ty = op.ty(this.mir_body, this.tcx).peel_refs();
+    ty.is_str()
+        || matches!(ty.kind(), TyKind::Adt(adt, _)
+            if this.tcx.def_path_str(adt.did()).ends_with("string::String"))
+}
+
+pub(crate) fn handle_bytes_new<'tcx, 'mir, 'ctx>(
+    this: &mut MIRParser<'tcx, 'mir, 'ctx>,
+    call: Call<'tcx>,
+) {
+    // Vec::new() / Vec::with_capacity(n) -> an empty Vec<u8>
+    let dest_ty = this.mir_body.local_decls[call.dest.local].ty;
+    if is_byte_buffer(this.tcx, dest_ty) {
+        let key = this.place_key(&call.dest);
+        this.curr.assign_string(&key, this.curr.static_bytes(&[]));
+    }
+}
+
+pub(crate) fn handle_vec_from_elem<'tcx, 'mir, 'ctx>(
+    this: &mut MIRParser<'tcx, 'mir, 'ctx>,
+    call: Call<'tcx>,
+) {
+    // vec![elem; n] -> alloc::vec::from_elem(elem, n)
+    if call.args.len() < 2 || !is_byte_buffer(this.tcx, this.mir_body.local_decls[call.dest.local].ty) {
+        return;
+    }
+    let key = this.place_key(&call.dest);
+    let elem = this.get_usize_from_operand(&call.args[0]).and_then(|b| u8::try_from(b).ok());
+    let value = match (elem, this.get_usize_from_operand(&call.args[1])) {
+        (Some(elem), Some(n)) => this.curr.static_bytes(&vec![elem; n]),
+        (Some(elem), None) => this.curr.repeated_byte(elem),
+        _ => this.curr.fresh_bytes(&key, None),
+    };
+    this.curr.assign_string(&key, value);
+    this.taint_through_call(&call, &call.args[1], &key);
+}
+
+pub(crate) fn handle_bytes_copy<'tcx, 'mir, 'ctx>(
+    this: &mut MIRParser<'tcx, 'mir, 'ctx>,
+    call: Call<'tcx>,
+) {
+    // <[u8]>::to_vec(&self) -> Vec<u8>, the same bytes
+    if call.args.is_empty() || !is_bytes_operand(this, &call.args[0]) {
+        return;
+    }
+    if let Some(bytes) = this.get_string_from_operand(&call.args[0]) {
+        let key = this.place_key(&call.dest);
+        this.curr.assign_string(&key, bytes);
+        this.taint_through_call(&call, &call.args[0], &key);
+    }
+}
+
+pub(crate) fn handle_vec_extend_from_slice<'tcx, 'mir, 'ctx>(
+    this: &mut MIRParser<'tcx, 'mir, 'ctx>,
+    call: Call<'tcx>,
+) {
+    // Vec::extend_from_slice(&mut self, other: &[u8]) and Vec::push(&mut self, byte)
+    if call.args.len() < 2 || !is_bytes_operand(this, &call.args[0]) {
+        return;
+    }
+    let (Operand::Copy(p) | Operand::Move(p)) = &call.args[0] else {
+        return;
+    };
+    let key = this.place_key(p);
+    let base = this.byte_buffer_base(&key);
+    let added = match call.args[1].ty(this.mir_body, this.tcx).kind() {
+        TyKind::Uint(_) => match this.get_usize_from_operand(&call.args[1]) {
+            Some(byte) => this.curr.static_bytes(&[byte as u8]),
+            None => this.curr.fresh_bytes("byte", Some(1)),
+        },
+        _ => match this.get_string_from_operand(&call.args[1]) {
+            Some(bytes) => bytes,
+            None => this.curr.fresh_bytes("bytes", None),
+        },
+    };
+    if let Some(old) = this.curr.get_string(&base).cloned() {
+        let new = this.curr.concat_strings(&old, &added);
+        this.write_byte_buffer(&key, new);
     }
+    this.taint_through_call(&call, &call.args[1], &base);
+}
+
+pub(crate) fn handle_copy_from_slice<'tcx, 'mir, 'ctx>(
+    this: &mut MIRParser<'tcx, 'mir, 'ctx>,
+    call: Call<'tcx>,
+) {
+    // <[u8]>::copy_from_slice(&mut self, src: &[u8])
+    if call.args.len() < 2 || !is_bytes_operand(this, &call.args[0]) {
+        return;
+    }
+    let (Operand::Copy(p) | Operand::Move(p)) = &call.args[0] else {
+        return;
+    };
+    let key = this.place_key(p);
+    let src = match this.get_string_from_operand(&call.args[1]) {
+        Some(src) => src,
+        None => this.curr.fresh_bytes("bytes", None),
+    };
+    this.write_byte_buffer(&key, src);
+    let base = this.byte_buffer_base(&key);
+    this.taint_through_call(&call, &call.args[1], &base);
+}
+
+pub(crate) fn handle_bytes_index<'tcx, 'mir, 'ctx>(
+    this: &mut MIRParser<'tcx, 'mir, 'ctx>,
+    call: Call<'tcx>,
+) {
+    // Index::index(&self, range) / IndexMut::index_mut(&mut self, range) of a byte buffer,
+    // the range being `..`, `..end`, `start..` or `start..end`
+    if call.args.len() < 2 || !is_bytes_operand(this, &call.args[0]) {
+        return;
+    }
+    let TyKind::Adt(range, _) = call.args[1].ty(this.mir_body, this.tcx).kind() else {
+        // a single byte
+        return;
+    };
+    let Some(buf) = this.get_string_from_operand(&call.args[0]) else {
+        return;
+    };
+    let dest_key = this.place_key(&call.dest);
+    let range_name = this.tcx.item_name(range.did());
+    let bound = this.get_usize_from_operand(&call.args[1]);
+    let known = match range_name.as_str() {
+        "RangeFull" => Some((0, None)),
+        "RangeTo" => bound.map(|end| (0, Some(end))),
+        "RangeFrom" => bound.map(|start| (start, None)),
+        _ => None,
+    };
+    let part = match known.and_then(|(start, end)| this.curr.slice_bytes(&buf, start, end)) {
+        Some(part) => part,
+        None => match range_name.as_str() {
+            "RangeTo" => {
+                let end = this.get_int_from_operand(&call.args[1]);
+                this.curr.bytes_part(&buf, BytesPart::Prefix, end.as_ref())
+            }
+            "RangeFrom" => {
+                let start = this.get_int_from_operand(&call.args[1]);
+                this.curr.bytes_part(&buf, BytesPart::Suffix, start.as_ref())
+            }
+            _ => this.curr.bytes_part(&buf, BytesPart::Inside, None),
+        },
+    };
+    this.curr.assign_string(&dest_key, part);
+
+    // writes through `&mut buf[range]` go into the buffer
+    if this.tcx.item_name(call.func_def_id).as_str() == "index_mut"
+        && let Operand::Copy(p) | Operand::Move(p) = &call.args[0]
+    {
+        let base = this.byte_buffer_base(&this.place_key(p));
+        let known = known.filter(|_| this.curr.get_string(&base).is_some());
+        this.byte_views.insert(dest_key.clone(), ByteView { base, range: known });
+    }
+    this.taint_through_call(&call, &call.args[0], &dest_key);
+}
+
+pub(crate) fn handle_compare<'tcx, 'mir, 'ctx>(
+    this: &mut MIRParser<'tcx, 'mir, 'ctx>,
+    call: Call<'tcx>,
+) {
+    // PartialEq::{eq, ne}(&a, &b) of text or of byte buffers, and
+    // <[u8]>::{starts_with, ends_with}(&self, needle) of byte buffers
+    let text = call.args.iter().all(|a| is_text_operand(this, a));
+    if call.args.len() < 2 || !(text || call.args.iter().all(|a| is_bytes_operand(this, a))) {
+        return;
+    }
+    let (Some(a), Some(b)) = (
+        this.get_string_from_operand(&call.args[0]),
+        this.get_string_from_operand(&call.args[1]),
+    ) else {
+        return;
+    };
+    let result = match this.tcx.item_name(call.func_def_id).as_str() {
+        "eq" => this.curr.string_eq(&a, &b),
+        "ne" => this.curr.not(&this.curr.string_eq(&a, &b)),
+        "starts_with" => b.prefix(&a),
+        "ends_with" => b.suffix(&a),
+        _ => return,
+    };
+    let key = this.place_key(&call.dest);
+    this.curr.assign_bool(&key, result);
+}
+
+pub(crate) fn handle_as_bytes<'tcx, 'mir, 'ctx>(
+    this: &mut MIRParser<'tcx, 'mir, 'ctx>,
+    call: Call<'tcx>,
+) {
+    // str::as_bytes(&self) / String::as_bytes(&self) / String::into_bytes(self), the UTF-8
+    // bytes of the text
+    if call.args.is_empty() {
+        return;
+    }
+    if let Some(s) = this.get_string_from_operand(&call.args[0]) {
+        let key = this.place_key(&call.dest);
+        let bytes = this.curr.encode_utf8(&s);
+        this.curr.assign_string(&key, bytes);
+        this.taint_through_call(&call, &call.args[0], &key);
+    }
+}
+
+pub(crate) fn handle_unwrap<'tcx, 'mir, 'ctx>(
+    this: &mut MIRParser<'tcx, 'mir, 'ctx>,
+    call: Call<'tcx>,
+) {
+    // Result::unwrap / Result::expect / ToString::to_string, the string the argument holds
+    // (`handle_string_from_utf8` puts the string in the `Result`)
+    if call.args.is_empty() {
+        return;
+    }
+    let name = this.tcx.item_name(call.func_def_id);
+    if !matches!(name.as_str(), "unwrap" | "expect" | "to_string") {
+        // `unwrap_or`, `unwrap_or_else`, ... return something else at times
+        return;
+    }
+    if let Some(s) = this.get_string_from_operand(&call.args[0]) {
+        let key = this.place_key(&call.dest);
+        this.curr.assign_string(&key, s);
+        this.taint_through_call(&call, &call.args[0], &key);
+    } else if name.as_str() == "to_string"
+        && let Some(n) = this.get_int_from_operand(&call.args[0])
+        && n == this.curr.process_id()
+    {
+        // the digits of the process id, e.g. for a path under `/proc`
+        let key = this.place_key(&call.dest);
+        let digits = this.curr.process_id_digits();
+        this.curr.assign_string(&key, digits);
+    }
+}
+
+/// `std::process::id` and `libc::getpid` return the id of the process (see `process_id`)
+pub(crate) fn handle_process_id<'tcx, 'mir, 'ctx>(
+    this: &mut MIRParser<'tcx, 'mir, 'ctx>,
+    call: Call<'tcx>,
+) {
+    let key = this.place_key(&call.dest);
+    let pid = this.curr.process_id();
+    let positive = this.curr.int_ge(&pid, &this.curr.static_int(1));
+    this.curr.add_constraint(positive);
+    this.curr.assign_int(&key, pid);
 }
@@ -0,0 +1,30 @@
+//!
+//! Class-hierarchy analysis: the methods a call through a trait object can run
+//!
+//! A call on a `dyn Trait` only names the trait method. Any impl of the trait the program can
+//! see may be behind the object, in this crate or in a dependency, so every one of them is a
+//! candidate.
+//!
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::TyCtxt;
+
+/// The methods a call to the trait method `method` can run: the method of every impl of the
+/// trait, in this crate and in the crates it depends on, or the trait's default method for
+/// the impls that do not override it. Empty when `method` is not a trait method.
+pub fn implementations(tcx: TyCtxt<'_>, method: DefId) -> Vec<DefId> {
+    let Some(trait_id) = tcx.trait_of_item(method) else {
+        return Vec::new();
+    };
+    let mut methods = Vec::new();
+    for impl_id in tcx.all_impls(trait_id) {
+        let implementor = tcx
+            .impl_item_implementor_ids(impl_id)
+            .get(&method)
+            .copied()
+            .unwrap_or(method);
+        if !methods.contains(&implementor) {
+            methods.push(implementor);
+        }
+    }
+    methods
+}
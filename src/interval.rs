@@ -0,0 +1,963 @@
+//!
+//! Intervals of the integer and boolean locals of a body, computed before it is executed
+//!
+//! A fast abstract interpretation over the MIR control flow graph: every integer and boolean
+//! place is given the range of values it can hold when a basic block is entered, constants
+//! being ranges of one value. Loops are run to a fixpoint, widened after a few rounds and
+//! narrowed back after it. The symbolic execution uses the result to drop the branches no
+//! execution can take, and to stand for every later iteration of a loop once it stops
+//! unrolling it (see `MIRParser::havoc_loop`).
+//!
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use rustc_middle::mir::{
+    AggregateKind, BasicBlock, BinOp, Body, BorrowKind, CastKind, ConstOperand, Local, Operand,
+    Place, ProjectionElem, Rvalue, Statement, StatementKind, SwitchTargets, TerminatorKind, UnOp,
+    UnwindAction,
+};
+use rustc_middle::ty::{Ty, TyCtxt, TyKind, TypingEnv};
+
+/// Rounds of a loop joined as they are before its intervals are widened
+const WIDEN_AFTER: u32 = 3;
+
+/// Rounds over the whole body after the fixpoint, to win back what widening gave up
+const NARROWING_ROUNDS: u32 = 2;
+
+/// A range of integers, unbounded on a side without a bound. Booleans are `0..=1`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Interval {
+    pub lo: Option<i128>,
+    pub hi: Option<i128>,
+}
+
+impl Interval {
+    pub const TOP: Interval = Interval { lo: None, hi: None };
+
+    pub fn new(lo: Option<i128>, hi: Option<i128>) -> Self {
+        Self { lo, hi }
+    }
+    pub fn constant(v: i128) -> Self {
+        Self::new(Some(v), Some(v))
+    }
+    /// The only value in the interval, when there is one
+    pub fn as_constant(&self) -> Option<i128> {
+        self.lo.filter(|lo| Some(*lo) == self.hi)
+    }
+    pub fn contains(&self, v: i128) -> bool {
+        self.lo.is_none_or(|lo| lo <= v) && self.hi.is_none_or(|hi| v <= hi)
+    }
+    /// Whether every value of `other` is in the interval
+    pub fn includes(&self, other: Interval) -> bool {
+        let lo = match (self.lo, other.lo) {
+            (None, _) => true,
+            (Some(a), Some(b)) => a <= b,
+            (Some(_), None) => false,
+        };
+        let hi = match (self.hi, other.hi) {
+            (None, _) => true,
+            (Some(a), Some(b)) => b <= a,
+            (Some(_), None) => false,
+        };
+        lo && hi
+    }
+    /// The smallest interval with the values of both
+    pub fn join(self, other: Interval) -> Self {
+        Self::new(
+            self.lo.zip(other.lo).map(|(a, b)| a.min(b)),
+            self.hi.zip(other.hi).map(|(a, b)| a.max(b)),
+        )
+    }
+    /// The values in both, `None` when there are none
+    pub fn meet(self, other: Interval) -> Option<Self> {
+        let lo = match (self.lo, other.lo) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        let hi = match (self.hi, other.hi) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        match (lo, hi) {
+            (Some(lo), Some(hi)) if lo > hi => None,
+            _ => Some(Self::new(lo, hi)),
+        }
+    }
+    /// `next`, an interval that holds this one, with the bounds that moved dropped, so that
+    /// a loop that keeps moving them gets to a fixpoint
+    pub fn widen(self, next: Interval) -> Self {
+        Self::new(
+            if self.lo == next.lo { self.lo } else { None },
+            if self.hi == next.hi { self.hi } else { None },
+        )
+    }
+    /// The interval without `v`, which can only be taken off at a bound. `None` when `v` was
+    /// its only value.
+    pub fn exclude(self, v: i128) -> Option<Self> {
+        if self.as_constant() == Some(v) {
+            return None;
+        }
+        Some(Self::new(
+            if self.lo == Some(v) {
+                v.checked_add(1)
+            } else {
+                self.lo
+            },
+            if self.hi == Some(v) {
+                v.checked_sub(1)
+            } else {
+                self.hi
+            },
+        ))
+    }
+}
+
+impl std::ops::Add for Interval {
+    type Output = Interval;
+    fn add(self, other: Interval) -> Interval {
+        let add = |a: Option<i128>, b: Option<i128>| a.zip(b).and_then(|(a, b)| a.checked_add(b));
+        Interval::new(add(self.lo, other.lo), add(self.hi, other.hi))
+    }
+}
+
+impl std::ops::Neg for Interval {
+    type Output = Interval;
+    fn neg(self) -> Interval {
+        Interval::new(
+            self.hi.and_then(i128::checked_neg),
+            self.lo.and_then(i128::checked_neg),
+        )
+    }
+}
+
+impl std::ops::Sub for Interval {
+    type Output = Interval;
+    fn sub(self, other: Interval) -> Interval {
+        self + -other
+    }
+}
+
+impl std::ops::Mul for Interval {
+    type Output = Interval;
+    fn mul(self, other: Interval) -> Interval {
+        let mul = |a: Option<i128>, b: Option<i128>| a.zip(b).and_then(|(a, b)| a.checked_mul(b));
+        if let (Some(a), Some(b), Some(c), Some(d)) = (self.lo, self.hi, other.lo, other.hi) {
+            let products = [
+                mul(Some(a), Some(c)),
+                mul(Some(a), Some(d)),
+                mul(Some(b), Some(c)),
+                mul(Some(b), Some(d)),
+            ];
+            if products.iter().all(Option::is_some) {
+                let products = products.map(Option::unwrap);
+                return Interval::new(
+                    products.iter().min().copied(),
+                    products.iter().max().copied(),
+                );
+            }
+        }
+        if self.lo >= Some(0) && other.lo >= Some(0) {
+            return Interval::new(mul(self.lo, other.lo), mul(self.hi, other.hi));
+        }
+        Interval::TOP
+    }
+}
+
+/// Intervals of the places of a body, by the keys of `MIRParser::place_key`. A place that is
+/// not in it can hold any value of its type.
+pub type IntervalState = HashMap<String, Interval>;
+
+fn join_states(a: &IntervalState, b: &IntervalState) -> IntervalState {
+    a.iter()
+        .filter_map(|(k, x)| b.get(k).map(|y| (k.clone(), x.join(*y))))
+        .collect()
+}
+
+fn widen_states(old: &IntervalState, new: &IntervalState) -> IntervalState {
+    new.iter()
+        .map(|(k, y)| (k.clone(), old.get(k).map_or(*y, |x| x.widen(*y))))
+        .collect()
+}
+
+/// What the pre-pass found out about a body
+#[derive(Debug, Clone, Default)]
+pub struct Intervals {
+    // Intervals when a block is entered, by block, `None` for blocks no execution gets to
+    entries: Vec<Option<IntervalState>>,
+    // The edges of the control flow graph some execution can take
+    feasible: HashSet<(BasicBlock, BasicBlock)>,
+    // The blocks of each loop, by the block it starts at (see `run`)
+    loops: HashMap<BasicBlock, HashSet<BasicBlock>>,
+    // Locals that can change behind our back, through a reference or a pointer to them
+    untracked: HashSet<Local>,
+}
+
+impl Intervals {
+    pub fn analyze<'tcx>(tcx: TyCtxt<'tcx>, body: &Body<'tcx>) -> Self {
+        Analysis::new(tcx, body).run()
+    }
+    /// The intervals when `bb` is entered, `None` when no execution gets to it
+    pub fn at_entry(&self, bb: BasicBlock) -> Option<&IntervalState> {
+        self.entries.get(bb.as_usize())?.as_ref()
+    }
+    /// Whether some execution can go from `from` to `to`
+    pub fn edge_feasible(&self, from: BasicBlock, to: BasicBlock) -> bool {
+        self.feasible.contains(&(from, to))
+    }
+    /// The blocks of the loop starting at `head`, `None` when no loop starts there
+    pub fn loop_blocks(&self, head: BasicBlock) -> Option<&HashSet<BasicBlock>> {
+        self.loops.get(&head)
+    }
+    /// The loops nested in the one starting at `head`, by the block they start at
+    pub fn inner_loops(&self, head: BasicBlock) -> impl Iterator<Item = BasicBlock> + '_ {
+        let outer = self.loops.get(&head);
+        self.loops
+            .iter()
+            .filter(move |(h, blocks)| {
+                outer.is_some_and(|o| **h != head && blocks.len() < o.len() && blocks.is_subset(o))
+            })
+            .map(|(h, _)| *h)
+    }
+
+    /// The locals the loop starting at `head` can change, `None` when no loop starts there
+    pub fn loop_locals(&self, body: &Body<'_>, head: BasicBlock) -> Option<Vec<Local>> {
+        let mut locals = HashSet::new();
+        // a write through a pointer, in the loop or in a function it calls, can change any
+        // local a pointer was taken to, before the loop as well
+        let mut indirect = false;
+        for b in self.loops.get(&head)? {
+            let data = &body.basic_blocks[*b];
+            for stmt in &data.statements {
+                let StatementKind::Assign(assign) = &stmt.kind else {
+                    continue;
+                };
+                locals.insert(assign.0.local);
+                indirect |= assign.0.is_indirect();
+                match &assign.1 {
+                    Rvalue::Ref(_, BorrowKind::Mut { .. }, place) | Rvalue::RawPtr(_, place) => {
+                        locals.insert(place.local);
+                        indirect |= place.is_indirect();
+                    }
+                    _ => {}
+                }
+            }
+            match &data.terminator().kind {
+                TerminatorKind::Call { destination, .. } => {
+                    locals.insert(destination.local);
+                    indirect = true;
+                }
+                TerminatorKind::Yield { resume_arg, .. } => {
+                    locals.insert(resume_arg.local);
+                    indirect |= resume_arg.is_indirect();
+                }
+                _ => {}
+            }
+        }
+        if indirect {
+            locals.extend(&self.untracked);
+        }
+        let mut locals: Vec<Local> = locals.into_iter().collect();
+        locals.sort();
+        Some(locals)
+    }
+}
+
+struct Analysis<'a, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    body: &'a Body<'tcx>,
+    typing_env: TypingEnv<'tcx>,
+    // Locals that can change behind our back, through a reference or a pointer to them
+    untracked: HashSet<Local>,
+}
+
+impl<'a, 'tcx> Analysis<'a, 'tcx> {
+    fn new(tcx: TyCtxt<'tcx>, body: &'a Body<'tcx>) -> Self {
+        let typing_env = body.typing_env(tcx);
+        let mut untracked = HashSet::new();
+        for data in body.basic_blocks.iter() {
+            for stmt in &data.statements {
+                let StatementKind::Assign(assign) = &stmt.kind else {
+                    continue;
+                };
+                match &assign.1 {
+                    // a shared borrow only lets a value change through a `Cell`
+                    Rvalue::Ref(_, BorrowKind::Shared | BorrowKind::Fake(_), place)
+                        if place.ty(body, tcx).ty.is_freeze(tcx, typing_env) => {}
+                    Rvalue::Ref(_, _, place) | Rvalue::RawPtr(_, place) => {
+                        untracked.insert(place.local);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Self {
+            tcx,
+            body,
+            typing_env,
+            untracked,
+        }
+    }
+
+    fn run(&self) -> Intervals {
+        let blocks = &self.body.basic_blocks;
+        let mut entries: Vec<Option<IntervalState>> = vec![None; blocks.len()];
+        entries[0] = Some(IntervalState::new());
+
+        // the worklist goes through the blocks in reverse postorder
+        let rpo = blocks.reverse_postorder();
+        let mut order = vec![usize::MAX; blocks.len()];
+        for (i, bb) in rpo.iter().enumerate() {
+            order[bb.as_usize()] = i;
+        }
+        let mut worklist = BTreeSet::from([0]);
+        let mut updates = vec![0u32; blocks.len()];
+        while let Some(i) = worklist.pop_first() {
+            let bb = rpo[i];
+            let Some(state) = entries[bb.as_usize()].clone() else {
+                continue;
+            };
+            for (succ, out) in self.successors(bb, state) {
+                let s = succ.as_usize();
+                // loops are widened where they go back to their head
+                let back_edge = order[s] <= i;
+                let new = match &entries[s] {
+                    None => out,
+                    Some(old) if back_edge && updates[s] >= WIDEN_AFTER => {
+                        widen_states(old, &join_states(old, &out))
+                    }
+                    Some(old) => join_states(old, &out),
+                };
+                if entries[s].as_ref() != Some(&new) {
+                    entries[s] = Some(new);
+                    updates[s] += 1;
+                    worklist.insert(order[s]);
+                }
+            }
+        }
+
+        // the fixpoint holds every execution, so does every round over it, each block taking
+        // what its predecessors hold by now
+        let predecessors = blocks.predecessors();
+        for _ in 0..NARROWING_ROUNDS {
+            for &bb in &rpo[1..] {
+                let mut entry: Option<IntervalState> = None;
+                for &pred in &predecessors[bb] {
+                    let Some(state) = entries[pred.as_usize()].clone() else {
+                        continue;
+                    };
+                    for (succ, out) in self.successors(pred, state) {
+                        if succ == bb {
+                            entry = Some(match entry {
+                                None => out,
+                                Some(acc) => join_states(&acc, &out),
+                            });
+                        }
+                    }
+                }
+                entries[bb.as_usize()] = entry;
+            }
+        }
+
+        let mut feasible = HashSet::new();
+        for &bb in rpo {
+            if let Some(state) = entries[bb.as_usize()].clone() {
+                for (succ, _) in self.successors(bb, state) {
+                    feasible.insert((bb, succ));
+                }
+            }
+        }
+
+        // a loop starts where an edge goes back in reverse postorder, every cycle has such an
+        // edge. It has the blocks that get back to its start without going through it.
+        let mut loops: HashMap<BasicBlock, HashSet<BasicBlock>> = HashMap::new();
+        for &bb in rpo {
+            for succ in blocks[bb].terminator().successors() {
+                if order[succ.as_usize()] > order[bb.as_usize()] {
+                    continue;
+                }
+                let body = loops.entry(succ).or_insert_with(|| HashSet::from([succ]));
+                let mut todo = vec![bb];
+                while let Some(b) = todo.pop() {
+                    if body.insert(b) {
+                        todo.extend(predecessors[b].iter().copied());
+                    }
+                }
+            }
+        }
+        Intervals {
+            entries,
+            feasible,
+            loops,
+            untracked: self.untracked.clone(),
+        }
+    }
+
+    // The edges out of `bb` some execution can take, with the intervals along them
+    fn successors(
+        &self,
+        bb: BasicBlock,
+        mut state: IntervalState,
+    ) -> Vec<(BasicBlock, IntervalState)> {
+        let data = &self.body.basic_blocks[bb];
+        for stmt in &data.statements {
+            self.statement(bb, &mut state, stmt);
+        }
+        let terminator = data.terminator();
+        match &terminator.kind {
+            TerminatorKind::SwitchInt { discr, targets } => self.switch(bb, &state, discr, targets),
+            TerminatorKind::Assert {
+                cond,
+                expected,
+                target,
+                unwind,
+                ..
+            } => {
+                let c = self.operand(&state, cond);
+                let e = i128::from(*expected);
+                let mut edges = Vec::new();
+                if c.is_none_or(|c| c.contains(e)) {
+                    let mut st = state.clone();
+                    if self.holds(bb, &mut st, cond, e).is_some() {
+                        edges.push((*target, st));
+                    }
+                }
+                if let UnwindAction::Cleanup(cleanup) = unwind
+                    && c.is_none_or(|c| c.contains(1 - e))
+                {
+                    edges.push((*cleanup, state));
+                }
+                edges
+            }
+            TerminatorKind::Call { destination, .. } => {
+                self.kill_place(&mut state, destination);
+                terminator
+                    .successors()
+                    .map(|s| (s, state.clone()))
+                    .collect()
+            }
+            TerminatorKind::Yield { resume_arg, .. } => {
+                self.kill_place(&mut state, resume_arg);
+                terminator
+                    .successors()
+                    .map(|s| (s, state.clone()))
+                    .collect()
+            }
+            // assembly can write anywhere
+            TerminatorKind::InlineAsm { .. } => terminator
+                .successors()
+                .map(|s| (s, IntervalState::new()))
+                .collect(),
+            _ => terminator
+                .successors()
+                .map(|s| (s, state.clone()))
+                .collect(),
+        }
+    }
+
+    fn switch(
+        &self,
+        bb: BasicBlock,
+        state: &IntervalState,
+        discr: &Operand<'tcx>,
+        targets: &SwitchTargets,
+    ) -> Vec<(BasicBlock, IntervalState)> {
+        let ty = discr.ty(self.body, self.tcx);
+        let Some(value) = self.operand(state, discr) else {
+            return targets
+                .all_targets()
+                .iter()
+                .map(|t| (*t, state.clone()))
+                .collect();
+        };
+        let mut edges = Vec::new();
+        let mut otherwise = Some(value);
+        for (bits, target) in targets.iter() {
+            let Some(v) = self.switch_value(ty, bits) else {
+                edges.push((target, state.clone()));
+                continue;
+            };
+            otherwise = otherwise.and_then(|o| o.exclude(v));
+            if !value.contains(v) {
+                continue;
+            }
+            let mut st = state.clone();
+            if self.holds(bb, &mut st, discr, v).is_some() {
+                edges.push((target, st));
+            }
+        }
+        if let Some(o) = otherwise {
+            let mut st = state.clone();
+            let feasible = match o.as_constant() {
+                Some(v) => self.holds(bb, &mut st, discr, v).is_some(),
+                None => {
+                    self.set(&mut st, discr, o);
+                    true
+                }
+            };
+            if feasible {
+                edges.push((targets.otherwise(), st));
+            }
+        }
+        edges
+    }
+
+    // Updates `state` for the executions where `op`, the last one set in `bb`, is `v`, and
+    // where the comparison it holds the result of goes that way. `None` when there are none.
+    fn holds(
+        &self,
+        bb: BasicBlock,
+        state: &mut IntervalState,
+        op: &Operand<'tcx>,
+        v: i128,
+    ) -> Option<()> {
+        self.set(state, op, Interval::constant(v));
+        let (Operand::Copy(place) | Operand::Move(place)) = op else {
+            return Some(());
+        };
+        if !place.projection.is_empty() || !op.ty(self.body, self.tcx).is_bool() {
+            return Some(());
+        }
+        match self.comparison(bb, place.local) {
+            Some((cmp, a, b)) => self.refine(bb, state, cmp, a, b, v != 0),
+            None => Some(()),
+        }
+    }
+
+    // The comparison `local` is set to by a statement of `bb`, when neither side of it changes
+    // after it
+    fn comparison(
+        &self,
+        bb: BasicBlock,
+        local: Local,
+    ) -> Option<(BinOp, &'a Operand<'tcx>, &'a Operand<'tcx>)> {
+        let statements = &self.body.basic_blocks[bb].statements;
+        let (i, assign) =
+            statements
+                .iter()
+                .enumerate()
+                .rev()
+                .find_map(|(i, stmt)| match &stmt.kind {
+                    StatementKind::Assign(assign) if assign.0.local == local => Some((i, assign)),
+                    _ => None,
+                })?;
+        let Rvalue::BinaryOp(op, operands) = &assign.1 else {
+            return None;
+        };
+        let is_comparison = matches!(
+            op,
+            BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge
+        );
+        if !assign.0.projection.is_empty() || !is_comparison {
+            return None;
+        }
+        let locals: Vec<Local> = [&operands.0, &operands.1]
+            .into_iter()
+            .filter_map(Operand::place)
+            .map(|p| p.local)
+            .collect();
+        let changed = statements[i + 1..].iter().any(|stmt| match &stmt.kind {
+            StatementKind::Assign(assign) => locals.contains(&assign.0.local),
+            StatementKind::StorageDead(l) => locals.contains(l),
+            _ => false,
+        });
+        (!changed).then_some((*op, &operands.0, &operands.1))
+    }
+
+    // Narrows `a` and `b` to the values where `a op b` is `holds`, `None` when there are none
+    fn refine(
+        &self,
+        bb: BasicBlock,
+        state: &mut IntervalState,
+        op: BinOp,
+        a: &Operand<'tcx>,
+        b: &Operand<'tcx>,
+        holds: bool,
+    ) -> Option<()> {
+        use BinOp::*;
+        let op = match (op, holds) {
+            (op, true) => op,
+            (Lt, false) => Ge,
+            (Le, false) => Gt,
+            (Gt, false) => Le,
+            (Ge, false) => Lt,
+            (Eq, false) => Ne,
+            (Ne, false) => Eq,
+            _ => return Some(()),
+        };
+        let (Some(x), Some(y)) = (self.operand(state, a), self.operand(state, b)) else {
+            return Some(());
+        };
+        let dec = |v: Option<i128>| v.and_then(|v| v.checked_sub(1));
+        let inc = |v: Option<i128>| v.and_then(|v| v.checked_add(1));
+        let (x, y) = match op {
+            Lt => (
+                x.meet(Interval::new(None, dec(y.hi)))?,
+                y.meet(Interval::new(inc(x.lo), None))?,
+            ),
+            Le => (
+                x.meet(Interval::new(None, y.hi))?,
+                y.meet(Interval::new(x.lo, None))?,
+            ),
+            Gt => (
+                x.meet(Interval::new(inc(y.lo), None))?,
+                y.meet(Interval::new(None, dec(x.hi)))?,
+            ),
+            Ge => (
+                x.meet(Interval::new(y.lo, None))?,
+                y.meet(Interval::new(None, x.hi))?,
+            ),
+            Eq => {
+                let both = x.meet(y)?;
+                (both, both)
+            }
+            Ne => (
+                y.as_constant().map_or(Some(x), |v| x.exclude(v))?,
+                x.as_constant().map_or(Some(y), |v| y.exclude(v))?,
+            ),
+            _ => return Some(()),
+        };
+        for (op, v) in [(a, x), (b, y)] {
+            for place in self.copies(bb, op) {
+                if let Some(key) = self.key(&place) {
+                    let own = state.get(&key).copied().unwrap_or(Interval::TOP);
+                    state.insert(key, own.meet(v)?);
+                }
+            }
+        }
+        Some(())
+    }
+
+    // The place of `op` and the places it is a copy of, as long as they keep the same value to
+    // the end of `bb`: comparisons are made on copies, `_5 = copy _3; _4 = Lt(move _5, ..)`
+    fn copies(&self, bb: BasicBlock, op: &Operand<'tcx>) -> Vec<Place<'tcx>> {
+        let statements = &self.body.basic_blocks[bb].statements;
+        let assigns = |local: Local, stmt: &Statement<'tcx>| matches!(&stmt.kind, StatementKind::Assign(assign) if assign.0.local == local);
+        let mut places = Vec::new();
+        let mut next = op.place();
+        while let Some(place) = next.take() {
+            places.push(place);
+            if !place.projection.is_empty() {
+                break;
+            }
+            let Some(i) = statements
+                .iter()
+                .rposition(|stmt| assigns(place.local, stmt))
+            else {
+                break;
+            };
+            if let StatementKind::Assign(assign) = &statements[i].kind
+                && let Rvalue::Use(Operand::Copy(src) | Operand::Move(src)) = &assign.1
+                && !statements[i + 1..]
+                    .iter()
+                    .any(|stmt| assigns(src.local, stmt))
+            {
+                next = Some(*src);
+            }
+        }
+        places
+    }
+
+    fn statement(&self, bb: BasicBlock, state: &mut IntervalState, stmt: &Statement<'tcx>) {
+        match &stmt.kind {
+            StatementKind::Assign(assign) => self.assign(bb, state, &assign.0, &assign.1),
+            StatementKind::SetDiscriminant { place, .. } | StatementKind::Deinit(place) => {
+                self.kill_place(state, place)
+            }
+            StatementKind::StorageDead(local) => {
+                self.kill_key(state, &local.as_usize().to_string())
+            }
+            _ => {}
+        }
+    }
+
+    fn assign(
+        &self,
+        bb: BasicBlock,
+        state: &mut IntervalState,
+        place: &Place<'tcx>,
+        rvalue: &Rvalue<'tcx>,
+    ) {
+        let key = self.key(place);
+        let ty = place.ty(self.body, self.tcx).ty;
+        // what is assigned is read before the place is written
+        let mut values = Vec::new();
+        match rvalue {
+            Rvalue::BinaryOp(
+                op @ (BinOp::AddWithOverflow | BinOp::SubWithOverflow | BinOp::MulWithOverflow),
+                operands,
+            ) => {
+                let range = self.type_range(operands.0.ty(self.body, self.tcx));
+                if let (Some(range), Some(v)) =
+                    (range, self.binary(state, *op, &operands.0, &operands.1))
+                {
+                    // the result is only used once the assertion that it did not overflow
+                    // passed, as it is then. Without the assertion (`overflowing_add` and
+                    // the like) it wraps around.
+                    let checked = key
+                        .as_deref()
+                        .is_some_and(|key| self.asserts_no_overflow(bb, key));
+                    let result = if checked {
+                        v.meet(range)
+                    } else {
+                        Some(if range.includes(v) { v } else { range })
+                    };
+                    if let Some(result) = result {
+                        values.push((".f0".to_string(), result));
+                    }
+                    if range.includes(v) {
+                        values.push((".f1".to_string(), Interval::constant(0)));
+                    }
+                }
+            }
+            Rvalue::Aggregate(kind, operands)
+                if matches!(**kind, AggregateKind::Tuple)
+                    || matches!(**kind, AggregateKind::Adt(_, _, _, _, None))
+                        && matches!(ty.kind(), TyKind::Adt(adt, _) if adt.is_struct()) =>
+            {
+                for (i, op) in operands.iter().enumerate() {
+                    if let Some(v) = self.operand(state, op) {
+                        values.push((format!(".f{i}"), v));
+                    }
+                }
+            }
+            _ => {
+                if let Some(v) = self.rvalue(state, rvalue, ty) {
+                    values.push((String::new(), v));
+                }
+            }
+        }
+        self.kill_place(state, place);
+        if let Some(key) = key {
+            for (field, v) in values {
+                state.insert(format!("{key}{field}"), v);
+            }
+        }
+    }
+
+    // Whether `bb` ends in the assertion that the overflow flag `{key}.f1` is not set
+    fn asserts_no_overflow(&self, bb: BasicBlock, key: &str) -> bool {
+        matches!(&self.body.basic_blocks[bb].terminator().kind,
+            TerminatorKind::Assert { cond, expected: false, .. }
+                if cond.place().and_then(|p| self.key(&p)) == Some(format!("{key}.f1")))
+    }
+
+    fn rvalue(
+        &self,
+        state: &IntervalState,
+        rvalue: &Rvalue<'tcx>,
+        ty: Ty<'tcx>,
+    ) -> Option<Interval> {
+        let range = self.type_range(ty)?;
+        let value = match rvalue {
+            Rvalue::Use(op) | Rvalue::Cast(CastKind::IntToInt, op, _) => self.operand(state, op),
+            Rvalue::UnaryOp(UnOp::Neg, op) => self.operand(state, op).map(|v| -v),
+            Rvalue::UnaryOp(UnOp::Not, op) if ty.is_bool() => {
+                self.operand(state, op).map(|v| Interval::constant(1) - v)
+            }
+            Rvalue::BinaryOp(op, operands) => self.binary(state, *op, &operands.0, &operands.1),
+            _ => None,
+        };
+        // a value out of the range of the type wraps around
+        Some(value.filter(|v| range.includes(*v)).unwrap_or(range))
+    }
+
+    fn binary(
+        &self,
+        state: &IntervalState,
+        op: BinOp,
+        a: &Operand<'tcx>,
+        b: &Operand<'tcx>,
+    ) -> Option<Interval> {
+        use BinOp::*;
+        let (x, y) = (self.operand(state, a)?, self.operand(state, b)?);
+        let nonneg = |v: Interval| v.lo >= Some(0);
+        Some(match op {
+            Add | AddUnchecked | AddWithOverflow => x + y,
+            Sub | SubUnchecked | SubWithOverflow => x - y,
+            Mul | MulUnchecked | MulWithOverflow => x * y,
+            Rem if nonneg(x) && y.lo > Some(0) => Interval::new(
+                Some(0),
+                match (x.hi, y.hi) {
+                    (Some(a), Some(b)) => Some(a.min(b - 1)),
+                    (a, b) => a.or(b.map(|b| b - 1)),
+                },
+            ),
+            BitAnd if nonneg(x) || nonneg(y) => {
+                let hi = match (nonneg(x).then_some(x.hi), nonneg(y).then_some(y.hi)) {
+                    (Some(Some(a)), Some(Some(b))) => Some(a.min(b)),
+                    (Some(a), Some(b)) => a.or(b),
+                    (Some(a), None) | (None, Some(a)) => a,
+                    (None, None) => None,
+                };
+                Interval::new(Some(0), hi)
+            }
+            Eq | Ne | Lt | Le | Gt | Ge => match compare(op, x, y) {
+                Some(holds) => Interval::constant(i128::from(holds)),
+                None => Interval::new(Some(0), Some(1)),
+            },
+            _ => return None,
+        })
+    }
+
+    // The interval of `op`, `None` when it is not an integer or a boolean
+    fn operand(&self, state: &IntervalState, op: &Operand<'tcx>) -> Option<Interval> {
+        match op {
+            Operand::Constant(c) => self.constant(c),
+            Operand::Copy(place) | Operand::Move(place) => {
+                let range = self.type_range(place.ty(self.body, self.tcx).ty)?;
+                let known = self.key(place).and_then(|key| state.get(&key).copied());
+                Some(known.and_then(|v| v.meet(range)).unwrap_or(range))
+            }
+        }
+    }
+
+    fn constant(&self, c: &ConstOperand<'tcx>) -> Option<Interval> {
+        let ty = c.ty();
+        let range = self.type_range(ty)?;
+        let Some(si) = c.const_.try_eval_scalar_int(self.tcx, self.typing_env) else {
+            return Some(range);
+        };
+        let v = if ty.is_signed() {
+            Some(si.to_int(si.size()))
+        } else {
+            i128::try_from(si.to_uint(si.size())).ok()
+        };
+        Some(v.map_or(range, Interval::constant))
+    }
+
+    // The value a `SwitchInt` on a `ty` compares with when its target is for `bits`
+    fn switch_value(&self, ty: Ty<'tcx>, bits: u128) -> Option<i128> {
+        if ty.is_integral() && ty.is_signed() {
+            let (size, _) = ty.int_size_and_signed(self.tcx);
+            return Some(size.sign_extend(bits));
+        }
+        i128::try_from(bits).ok()
+    }
+
+    // The values of `ty`, `None` when it is not an integer or a boolean
+    fn type_range(&self, ty: Ty<'tcx>) -> Option<Interval> {
+        match ty.kind() {
+            TyKind::Bool => Some(Interval::new(Some(0), Some(1))),
+            TyKind::Int(_) | TyKind::Uint(_) => {
+                let (size, signed) = ty.int_size_and_signed(self.tcx);
+                Some(if signed {
+                    Interval::new(Some(size.signed_int_min()), Some(size.signed_int_max()))
+                } else {
+                    Interval::new(Some(0), i128::try_from(size.unsigned_int_max()).ok())
+                })
+            }
+            _ => None,
+        }
+    }
+
+    // The key of `place` in an `IntervalState`, `None` when we do not follow its value
+    fn key(&self, place: &Place<'tcx>) -> Option<String> {
+        if self.untracked.contains(&place.local) {
+            return None;
+        }
+        let mut key = place.local.as_usize().to_string();
+        for elem in place.projection {
+            match elem {
+                ProjectionElem::Field(f, _) => key.push_str(&format!(".f{}", f.as_usize())),
+                _ => return None,
+            }
+        }
+        Some(key)
+    }
+
+    fn set(&self, state: &mut IntervalState, op: &Operand<'tcx>, v: Interval) {
+        if let Some(key) = op.place().and_then(|p| self.key(&p)) {
+            state.insert(key, v);
+        }
+    }
+
+    // Forgets what `place` and the places in it hold
+    fn kill_place(&self, state: &mut IntervalState, place: &Place<'tcx>) {
+        // what is behind a reference is not followed
+        if place.is_indirect() {
+            return;
+        }
+        match self.key(place) {
+            Some(key) => self.kill_key(state, &key),
+            None => self.kill_key(state, &place.local.as_usize().to_string()),
+        }
+    }
+
+    fn kill_key(&self, state: &mut IntervalState, key: &str) {
+        let prefix = format!("{key}.");
+        state.retain(|k, _| k != key && !k.starts_with(&prefix));
+    }
+}
+
+// Whether `a op b` holds for all the values of `x` and `y`, or for none of them
+fn compare(op: BinOp, x: Interval, y: Interval) -> Option<bool> {
+    // every value of `x` is below every value of `y`
+    let below = |x: Interval, y: Interval, strict: bool| match (x.hi, y.lo) {
+        (Some(a), Some(b)) => {
+            if strict {
+                a < b
+            } else {
+                a <= b
+            }
+        }
+        _ => false,
+    };
+    use BinOp::*;
+    match op {
+        Lt if below(x, y, true) => Some(true),
+        Lt if below(y, x, false) => Some(false),
+        Le if below(x, y, false) => Some(true),
+        Le if below(y, x, true) => Some(false),
+        Gt => compare(Lt, y, x),
+        Ge => compare(Le, y, x),
+        Eq if x.as_constant().is_some() && x.as_constant() == y.as_constant() => Some(true),
+        Eq if x.meet(y).is_none() => Some(false),
+        Ne => compare(Eq, x, y).map(|eq| !eq),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_interval() {
+    let i = |lo: i128, hi: i128| Interval::new(Some(lo), Some(hi));
+    assert_eq!(i(0, 3) + i(1, 1), i(1, 4));
+    assert_eq!(i(-2, 3) * i(-1, 2), i(-4, 6));
+    assert_eq!(i(0, 3).meet(i(4, 5)), None);
+    assert_eq!(i(0, 3).exclude(0), Some(i(1, 3)));
+    assert_eq!(Interval::constant(2).exclude(2), None);
+    assert_eq!(i(0, 0).widen(i(0, 1)), Interval::new(Some(0), None));
+    assert_eq!(compare(BinOp::Lt, i(0, 9), i(10, 10)), Some(true));
+    assert_eq!(compare(BinOp::Lt, i(0, 10), i(10, 10)), None);
+    assert_eq!(compare(BinOp::Ne, i(3, 3), i(4, 8)), Some(true));
+}
+
+#[test]
+fn test_overflowing_add() {
+    // `overflowing_add` wraps around: no assertion follows it once it is inlined, and `sum` can
+    // be below `x`
+    let source = r#"
+        #[inline(never)]
+        pub fn wrapped() {}
+
+        pub fn wraps(x: u8, y: u8) {
+            if x >= 200 && y < 60 {
+                let (sum, _) = x.overflowing_add(y);
+                if sum < 100 {
+                    wrapped();
+                }
+            }
+        }
+    "#;
+    crate::callback::with_tcx("overflowing_add", source, &["-Copt-level=2"], |tcx| {
+        let wraps = tcx
+            .hir_body_owners()
+            .find(|def_id| tcx.item_name(def_id.to_def_id()).as_str() == "wraps")
+            .unwrap();
+        let body = tcx.optimized_mir(wraps);
+        let intervals = Intervals::analyze(tcx, body);
+        let mut calls = body
+            .basic_blocks
+            .iter_enumerated()
+            .filter(|(_, data)| matches!(data.terminator().kind, TerminatorKind::Call { .. }));
+        assert!(calls.any(|(bb, _)| intervals.at_entry(bb).is_some()));
+    });
+}
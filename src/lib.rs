@@ -14,12 +14,21 @@ extern crate rustc_session;
 extern crate rustc_span;
 
 extern crate rustc_abi;
+extern crate rustc_ast;
 extern crate rustc_data_structures;
+extern crate rustc_expand;
 extern crate rustc_middle;
 
+pub mod annotations;
 pub mod callback;
+pub mod cli;
 pub mod handlers;
+pub mod hierarchy;
+pub mod interval;
 pub mod operand;
 pub mod parser;
+pub mod report;
+pub mod sarif;
 pub mod settings;
+pub mod summary;
 pub mod symexec;
@@ -4,18 +4,19 @@
 #![feature(mapped_lock_guards)]
 #![allow(dead_code)]
 
-use clap::Parser;
-use clap::ValueEnum;
-
 use std::fs;
 use std::fs::{File, FileType};
-use std::io::ErrorKind;
+use std::io::{ErrorKind, Write};
 use std::path::{Path, PathBuf};
 
 const DEF_ID_PATH_BUF: usize = 5175;
 
-use lhs::callback::LCallback;
+use lhs::callback::{LCallback, PassThrough};
+use lhs::cli::{Args, Format, parse_args};
 use lhs::parser::MIRParser;
+use lhs::report::{merge_reports, to_csv};
+use lhs::sarif::sarif_log;
+use lhs::settings::{Settings, settings};
 use lhs::symexec;
 
 // -------------------- START RUSTC PORTION --------------------
@@ -52,48 +53,109 @@ use rustc_driver::{run_compiler, Callbacks};
 // -------------------- END RUSTC PORTION --------------------
 
 fn main() {
-    let args = std::env::args().skip(1).collect::<Vec<String>>();
-    get_callback_mir(args);
+    let (args, rustc_args) = parse_args(std::env::args().skip(1).collect());
+    match Settings::discover(args.config.as_deref()) {
+        Ok(s) => lhs::settings::init(s),
+        Err(e) => {
+            eprintln!("error: {e}");
+            process::exit(1);
+        }
+    }
+    if let Some(dir) = &args.merge {
+        merge(dir, &args);
+        return;
+    }
+    get_callback_mir(args, rustc_args);
 }
 
-#[derive(Parser, Debug)]
-#[command(name = "LHS: Leveraging (not) HIR via Symbolic execution")]
-#[command(version = "1.0.1")]
-#[command(about = "A command line utility for searching for analyzing a given Rust code's MIR and verifying proc/self/mem safety", long_about = None)]
-pub struct Args {
-    /// Filepath of Rust code
-    #[arg(short, long, required = true)]
-    pub source: String,
-
-    /// Tool action options
-    #[arg(short, long, required = true, value_enum)]
-    pub action: Action,
-
-    /// Toggle matching with numeric variables
-    #[arg(long)]
-    pub numeric: bool,
+/// Prints the reports the crates wrote to `dir` as one, in `--format`
+fn merge(dir: &Path, args: &Args) {
+    let reports = merge_reports(dir).unwrap_or_else(|e| {
+        eprintln!("error: could not read the reports in {}: {e}", dir.display());
+        process::exit(1);
+    });
+    let mut out = std::io::stdout().lock();
+    let written = match args.format {
+        Format::Csv => write!(out, "{}", to_csv(&reports)),
+        Format::Json => reports
+            .iter()
+            .try_for_each(|report| writeln!(out, "{}", serde_json::to_string(report).unwrap())),
+        Format::Sarif => {
+            let log = sarif_log(&reports, &settings().sink_patterns(), |sink| args.sink_level(sink));
+            writeln!(out, "{}", serde_json::to_string_pretty(&log).unwrap())
+        }
+    };
+    // the reader went away (`lhs --merge dir | head`), nobody is left to tell
+    match written.and_then(|()| out.flush()) {
+        Err(e) if e.kind() != ErrorKind::BrokenPipe => {
+            eprintln!("error: could not write the merged reports: {e}");
+            process::exit(1);
+        }
+        _ => {}
+    }
 }
 
-#[derive(Debug, ValueEnum, Clone, Eq, PartialEq)]
-pub enum Action {
-    Trace,
-    Blocks,
-    Local,
-    Query,
-    Callback,
+fn get_callback_mir(args: Args, rustc_args: Vec<String>) {
+    // A misspelled sink would silently stay at the default level, which defeats `--deny`
+    for sink in args.named_sinks() {
+        if !settings().sink_patterns().iter().any(|(path, _, _)| *path == sink) {
+            eprintln!("error: `{sink}` is not a configured sink, expected `all` or a sink path");
+            process::exit(1);
+        }
+    }
+
+    // Run by hand without a rustc command line: analyze the given source file on its own
+    if rustc_args.is_empty() {
+        let Some(source) = args.source.clone() else {
+            eprintln!("error: expected `--source <FILE>` or a rustc command line after `--`");
+            process::exit(1);
+        };
+        let rustc_args = standalone_rustc_args(&source);
+        let mut callbacks = LCallback::standalone(args);
+        process::exit(rustc_driver::catch_with_exit_code(|| {
+            run_compiler(&rustc_args, &mut callbacks)
+        }));
+    }
+
+    // rustc_args[0] is the rustc binary cargo asked us to wrap; run_compiler skips it
+    let exit_code = if args.in_scope() {
+        let mut callbacks = LCallback::new(args);
+        rustc_driver::catch_with_exit_code(|| run_compiler(&rustc_args, &mut callbacks))
+    } else if args.summarize_deps {
+        let mut callbacks = LCallback::summarize_only(args);
+        rustc_driver::catch_with_exit_code(|| run_compiler(&rustc_args, &mut callbacks))
+    } else {
+        rustc_driver::catch_with_exit_code(|| run_compiler(&rustc_args, &mut PassThrough))
+    };
+    process::exit(exit_code);
 }
 
-fn get_callback_mir(args: Vec<String>) {
-    // let out = process::Command::new("rustc")
-    //     .arg("--print=sysroot")
-    //     .current_dir(".")
-    //     .output()
-    //     .unwrap();
-    // let sysroot = str::from_utf8(&out.stdout).unwrap().trim().to_string();
-    // let rustc_args: Vec<String> = args;
-
-    let mut callbacks = LCallback::new();
-    // let args = std::env::args().collect::<Vec<String>>();
-    // args is a Vec<String>?
-    run_compiler(&args, &mut callbacks);
+/// The rustc command line for analyzing a single file: the examples are plain functions
+/// without a `main`, so they are compiled as a library against the sysroot of the toolchain
+/// LHS was built with.
+fn standalone_rustc_args(source: &str) -> Vec<String> {
+    let mut rustc = process::Command::new("rustc");
+    if let Some(toolchain) = option_env!("RUSTUP_TOOLCHAIN") {
+        rustc.env("RUSTUP_TOOLCHAIN", toolchain);
+    }
+    let out = match rustc.arg("--print=sysroot").current_dir(".").output() {
+        Ok(out) if out.status.success() => out,
+        _ => {
+            eprintln!("error: could not find the sysroot with `rustc --print=sysroot`");
+            process::exit(1);
+        }
+    };
+    let sysroot = str::from_utf8(&out.stdout).unwrap().trim().to_string();
+    [
+        "rustc",
+        source,
+        "--crate-type",
+        "lib",
+        "--edition",
+        "2021",
+        "--sysroot",
+        &sysroot,
+    ]
+    .map(String::from)
+    .to_vec()
 }
@@ -1,22 +1,19 @@
 use rustc_abi::Size;
 use rustc_hir::def_id::DefId;
 use rustc_middle::mir::interpret::{AllocRange, ConstAllocation, GlobalAlloc, Pointer, Scalar};
-use rustc_middle::mir::{Const, ConstValue, Local, Operand, Place};
+use rustc_middle::mir::{Const, ConstValue, Local, Operand, Place, Rvalue, StatementKind};
 use rustc_middle::ty::ScalarInt;
-use rustc_middle::ty::{ParamEnv, Ty, TyCtxt, TyKind};
+use rustc_middle::ty::{ParamEnv, Ty, TyCtxt, TyKind, UintTy};
 
 // Get the DefID associated with a given Operand (function)
 pub fn get_operand_def_id<'tcx>(operand: &Operand<'tcx>) -> Option<DefId> {
     match operand {
         Operand::Constant(c) => {
-            match &c.const_ {
-                // Only `Const::Val` can hold a fully‑evaluated constant with its Ty.
-                Const::Val(_val, ty) => {
-                    if let TyKind::FnDef(def_id, _generic_args) = ty.kind() {
-                        return Some(*def_id); // Preserve the full `DefId`
-                    }
-                }
-                _ => {}
+            // Only `Const::Val` can hold a fully‑evaluated constant with its Ty.
+            if let Const::Val(_val, ty) = &c.const_
+                && let TyKind::FnDef(def_id, _generic_args) = ty.kind()
+            {
+                return Some(*def_id); // Preserve the full `DefId`
             }
             None
         }
@@ -63,12 +60,79 @@ pub fn get_operand_const_string<'tcx>(operand: &Operand<'tcx>) -> Option<String>
         _ => return None,
     };
 
-    match String::from_utf8(bytes) {
-        Ok(s) => Some(s),
-        Err(e) => None,
+    String::from_utf8(bytes).ok()
+}
+
+// Extract the bytes of a byte string literal (`b"..."`, a `&[u8; N]`) or of a
+// `&'static [u8]` constant embedded in an `Operand`.
+pub fn get_operand_const_bytes<'tcx>(tcx: TyCtxt<'tcx>, operand: &Operand<'tcx>) -> Option<Vec<u8>> {
+    let Operand::Constant(c) = operand else {
+        return None;
+    };
+    if let Some(bytes) = find_in_promoted(tcx, operand, |o| get_operand_const_bytes(tcx, o)) {
+        return Some(bytes);
+    }
+    let Const::Val(val, ty) = c.const_ else {
+        return None;
+    };
+    let TyKind::Ref(_, inner, _) = ty.kind() else {
+        return None;
+    };
+    match (inner.kind(), val) {
+        (TyKind::Slice(elem), ConstValue::Slice { data, meta }) if is_u8(*elem) => {
+            let range = AllocRange {
+                start: Size::from_bytes(0),
+                size: Size::from_bytes(meta),
+            };
+            Some(data.0.get_bytes_unchecked(range).to_vec())
+        }
+        (TyKind::Array(elem, len), ConstValue::Scalar(Scalar::Ptr(ptr, _))) if is_u8(*elem) => {
+            let len = len.try_to_target_usize(tcx)?;
+            let (prov, offset) = ptr.into_parts();
+            let GlobalAlloc::Memory(alloc) = tcx.global_alloc(prov.alloc_id()) else {
+                return None;
+            };
+            let range = AllocRange {
+                start: offset,
+                size: Size::from_bytes(len),
+            };
+            Some(alloc.inner().get_bytes_unchecked(range).to_vec())
+        }
+        _ => None,
     }
 }
 
+// The first value `f` finds among the operands the promoted constant `operand` is built from.
+// `&b"..."` and `&"..."` are promoted, the literal is in the body of the promoted constant.
+pub fn find_in_promoted<'tcx, T>(
+    tcx: TyCtxt<'tcx>,
+    operand: &Operand<'tcx>,
+    f: impl Fn(&Operand<'tcx>) -> Option<T>,
+) -> Option<T> {
+    let Operand::Constant(c) = operand else {
+        return None;
+    };
+    let Const::Unevaluated(uv, _) = c.const_ else {
+        return None;
+    };
+    let body = &tcx.promoted_mir(uv.def)[uv.promoted?];
+    body.basic_blocks
+        .iter()
+        .flat_map(|block| &block.statements)
+        .find_map(|statement| match &statement.kind {
+            StatementKind::Assign(assign) => match &assign.1 {
+                Rvalue::Use(operand) => f(operand),
+                _ => None,
+            },
+            _ => None,
+        })
+}
+
+// Whether `ty` is `u8`
+pub fn is_u8(ty: Ty<'_>) -> bool {
+    matches!(ty.kind(), TyKind::Uint(UintTy::U8))
+}
+
 // Get the `Local` associated with an Operand if of Move variant
 pub fn get_operand_local<'tcx>(operand: &Operand<'tcx>) -> Option<usize> {
     match operand {
@@ -111,14 +175,14 @@ pub fn extract_string_from_const<'tcx>(
         }
     };
 
-    return Some(a);
+    Some(a)
 }
 
 pub fn get_operand_span(operand: &Operand) -> Option<rustc_span::Span> {
     match operand {
         Operand::Copy(_place) => {
             // println!("get_operand_span: Unsupported, This function currently caters only for constants. ");
-            return None;
+            None
         }
         Operand::Move(place) => {
             // println!("get_operand_span: Unsupported, This function currently caters only for constants. ");
@@ -126,7 +190,7 @@ pub fn get_operand_span(operand: &Operand) -> Option<rustc_span::Span> {
         }
         Operand::Constant(place) => {
             let const_span = place.span;
-            return Some(const_span);
+            Some(const_span)
         }
     }
 }
@@ -1,31 +1,47 @@
 use rustc_middle::mir::{
-    BasicBlock, BinOp, Body, CallSource, Operand, Place, ProjectionElem, Rvalue, StatementKind,
-    SwitchTargets, TerminatorKind, UnwindAction,
+    AggregateKind, BasicBlock, BinOp, Body, CallSource, ConstOperand, Local, Operand, Place,
+    ProjectionElem, Rvalue, StatementKind, SwitchTargets, TerminatorKind, UnwindAction,
+    VarDebugInfoContents,
 };
 
+use rustc_hir::def::DefKind;
 use rustc_hir::def_id::DefId;
-use rustc_middle::ty::{data_structures::IndexMap, TyCtxt};
+use rustc_middle::ty::{
+    data_structures::IndexMap, EarlyBinder, Instance, InstanceKind, Ty, TyCtxt, TyKind,
+    TypeVisitableExt, TypingEnv,
+};
 
-use rustc_span::Span;
+use rustc_span::source_map::Spanned;
+use rustc_span::{FileNameDisplayPreference, Span};
 
 use z3::ast::Ast;
 use z3::SatResult;
 
+use crate::report::{Finding, FlowStep, Location, MatchKind, Tier, Witness};
 use crate::operand::{
-    get_operand_const_string, get_operand_def_id, get_operand_local, get_operand_span,
+    find_in_promoted, get_operand_const_bytes, get_operand_const_string, get_operand_def_id,
+    get_operand_span, is_u8,
 };
-// TODO: update to use SOURCE_FUNCTIONS and SINK_FUNCTION_ARGS
-use crate::settings::{ENV_VARS_TO_TRACK, MAX_LOOP_ITER, SINK_FUNCTION_ARGS, SOURCE_FUNCTIONS};
-use crate::symexec::SymExecBool as SymExec;
+use crate::annotations::annotations;
+use crate::hierarchy::implementations;
+use crate::interval::Intervals;
+use crate::settings::settings;
+use crate::summary::{FunctionSummary, Summaries};
+use crate::symexec::{IntTy, SymExecBool as SymExec, TaintStep};
 
 use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 
 use crate::handlers::{
-    generic_string_handler, handle_deref_generic, handle_deref_mut, handle_fmt_arg_new_display,
-    handle_fmt_arguments_new_v1, handle_fmt_format, handle_from_trait, handle_generic_source,
-    handle_path_join, handle_path_new, handle_path_to_path_buf, handle_pathbuf_from,
-    handle_pathbuf_push, handle_read_into_buf, handle_result_unwrap_or_default, handle_string_from,
-    handle_string_from_utf8, handle_string_from_utf8_lossy,
+    generic_string_handler, handle_as_bytes, handle_bytes_copy, handle_bytes_index,
+    handle_bytes_new, handle_compare, handle_copy_from_slice, handle_deref_generic,
+    handle_deref_mut, handle_fmt_arg_new_display, handle_fmt_arguments_new_v1, handle_fmt_format,
+    handle_from_trait, handle_generic_source, handle_path_join, handle_path_new,
+    handle_path_to_path_buf, handle_pathbuf_from, handle_pathbuf_push, handle_process_id,
+    handle_read_into_buf,
+    handle_result_unwrap_or_default, handle_sanitizer, handle_string_from,
+    handle_string_from_utf8, handle_string_from_utf8_lossy, handle_unwrap,
+    handle_vec_extend_from_slice, handle_vec_from_elem,
 };
 
 #[derive(Clone, Copy, Debug)]
@@ -34,6 +50,163 @@ pub struct SinkInformation {
     pub forbidden_val: &'static str,
 }
 
+const DEF_ID_PATH_BUF: usize = 5175;
+
+/// A finding together with the span its diagnostic points at
+#[derive(Clone, Debug)]
+pub struct SinkHit {
+    pub span: Span,
+    pub finding: Finding,
+    /// Spans of the calls in `finding.call_chain`
+    pub call_spans: Vec<Span>,
+    /// Spans of the steps in `finding.flow`
+    pub flow_spans: Vec<Span>,
+}
+
+/// Identifies `def_id` in the summaries, in its own crate and in the crates that use it
+pub fn summary_key(tcx: TyCtxt<'_>, def_id: DefId) -> String {
+    let (hi, lo) = tcx.def_path_hash(def_id).0.split();
+    format!("{hi:016x}{lo:016x}")
+}
+
+/// The MIR of `instance`: the body of its function, with the generic parameters replaced by
+/// the types it is instantiated with
+pub fn instance_body<'tcx>(tcx: TyCtxt<'tcx>, instance: Instance<'tcx>) -> &'tcx Body<'tcx> {
+    let body = tcx.instance_mir(instance.def);
+    if instance.args.is_empty() {
+        return body;
+    }
+    tcx.arena.alloc(instance.instantiate_mir_and_normalize_erasing_regions(
+        tcx,
+        TypingEnv::fully_monomorphized(),
+        EarlyBinder::bind(body.clone()),
+    ))
+}
+
+/// Keys the body of the closure or coroutine `def_id` reads its captures under, with `.f<i>`
+/// appended: its environment is `_1`, a reference to it, or a pinned reference for coroutines,
+/// and is often copied to other locals before a capture is read.
+fn env_keys(mir_body: &Body<'_>, def_id: DefId) -> Vec<String> {
+    let is_env = |ty: &TyKind<'_>| {
+        matches!(ty, TyKind::Closure(id, _) | TyKind::Coroutine(id, _) | TyKind::CoroutineClosure(id, _) if *id == def_id)
+    };
+    let mut keys = Vec::new();
+    for (local, local_decl) in mir_body.local_decls.iter_enumerated() {
+        let key = local.as_usize().to_string();
+        match local_decl.ty.kind() {
+            ty if is_env(ty) => keys.push(key),
+            TyKind::Ref(_, ty, _) if is_env(ty.kind()) => keys.push(format!("{key}*")),
+            // `Pin<&mut Self>`
+            TyKind::Adt(_, args)
+                if local.as_usize() == 1
+                    && args.types().next().is_some_and(
+                        |ty| matches!(ty.kind(), TyKind::Ref(_, ty, _) if is_env(ty.kind())),
+                    ) =>
+            {
+                keys.push("1.f0*".to_string())
+            }
+            _ => {}
+        }
+    }
+    keys
+}
+
+/// Declares the locals of `mir_body` we can model as fresh, untainted variables
+pub fn declare_locals<'tcx>(ev: &mut SymExec<'_>, mir_body: &Body<'tcx>, numeric: bool) {
+    for (local, local_decl) in mir_body.local_decls.iter_enumerated() {
+        match local_decl.ty.kind() {
+            TyKind::Int(_) | TyKind::Uint(_) if !numeric => {}
+            TyKind::Int(_) => ev.create_int(local.as_usize().to_string().as_str()),
+            TyKind::Uint(_) => ev.create_int(local.as_usize().to_string().as_str()),
+            TyKind::Str => ev.create_uninterpreted_string(local.as_usize().to_string().as_str()),
+            TyKind::Char => ev.create_uninterpreted_string(local.as_usize().to_string().as_str()),
+            TyKind::Ref(_, ty, _) => {
+                // byte slices are strings too, see `SymExec::static_bytes`
+                let bytes = matches!(ty.kind(), TyKind::Slice(elem) if is_u8(*elem));
+                if ty.is_str() || bytes {
+                    ev.create_uninterpreted_string(local.as_usize().to_string().as_str())
+                }
+            }
+            TyKind::Bool => ev.create_uninterpreted_bool(local.as_usize().to_string().as_str()),
+            TyKind::Adt(fields, _) => {
+                let def_ids = fields
+                    .all_fields()
+                    .map(|field| field.did)
+                    .collect::<Vec<_>>();
+
+                // Now we have a Vec containing all DefIds
+                for def_id in &def_ids {
+                    if def_id.index.as_usize() == DEF_ID_PATH_BUF {
+                        ev.create_uninterpreted_string(local.as_usize().to_string().as_str());
+                        break;
+                    }
+                }
+            }
+            // _ => println!("Unsupported Type: {}", local_decl.ty),
+            _ => {
+                // println!("Unsupported Type: {}", local_decl.ty);
+            }
+        }
+    }
+}
+
+/// Whether `ty`, behind any references, is a byte buffer: `[u8; N]`, `[u8]` or `Vec<u8>`
+pub(crate) fn is_byte_buffer<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> bool {
+    match ty.peel_refs().kind() {
+        TyKind::Slice(elem) | TyKind::Array(elem, _) => is_u8(*elem),
+        TyKind::Adt(adt, args) => {
+            tcx.def_path_str(adt.did()).ends_with("vec::Vec")
+                && args.types().next().is_some_and(is_u8)
+        }
+        _ => false,
+    }
+}
+
+/// A `&mut [u8]` to part of a byte buffer: what is written through it goes into `base`, at
+/// the bytes `start..end` (`start..` without an `end`) when they are known
+#[derive(Clone, Debug)]
+pub(crate) struct ByteView {
+    pub base: String,
+    pub range: Option<(usize, Option<usize>)>,
+}
+
+/// Adds `new` to `hits`, or merges it into the hit of the same call through the same chain
+pub fn merge_hit(hits: &mut Vec<SinkHit>, new: SinkHit) {
+    if let Some(hit) = hits.iter_mut().find(|h| {
+        h.span == new.span
+            && h.finding.sink == new.finding.sink
+            && h.finding.forbidden == new.finding.forbidden
+            && h.finding.call_chain == new.finding.call_chain
+    }) {
+        hit.finding.tainted |= new.finding.tainted;
+        hit.finding.match_kind = hit.finding.match_kind.min(new.finding.match_kind);
+        hit.finding.tier = hit.finding.tier.min(new.finding.tier);
+        if hit.finding.witness.is_empty() {
+            hit.finding.witness = new.finding.witness;
+        }
+        if hit.finding.flow.is_empty() {
+            hit.finding.flow = new.finding.flow;
+            hit.flow_spans = new.flow_spans;
+        }
+        return;
+    }
+    hits.push(new);
+}
+
+/// The source location of `span`, as written to the report
+pub fn span_location(tcx: TyCtxt<'_>, span: Span) -> Location {
+    let sm = tcx.sess.source_map();
+    let lo = sm.lookup_char_pos(span.lo());
+    let hi = sm.lookup_char_pos(span.hi());
+    Location {
+        file: lo.file.name.display(FileNameDisplayPreference::Local).to_string(),
+        line_lo: lo.line,
+        col_lo: lo.col.0 + 1,
+        line_hi: hi.line,
+        col_hi: hi.col.0 + 1,
+    }
+}
+
 pub struct MIRParser<'tcx, 'mir, 'ctx>
 where
     'mir: 'tcx,
@@ -45,18 +218,39 @@ where
     stack: Vec<(SymExec<'ctx>, BasicBlock)>,
     path_count: u32,
 
-    // Loop handling: track how many times we've visited each basic block
-    visit_counts: HashMap<BasicBlock, u32>,
+    // Intervals of the integer and boolean places, the edges no execution takes and the loops
+    intervals: Intervals,
 
-    // Collection of all dangerous write locations found during analysis
-    // TODO: Vec<AnalysisResult>
-    // Or: HashMap<(String, Operand), AnalysisResult>
-    dangerous_spans: HashMap<(String, String), Vec<Span>>,
+    // Collection of all sink hits found during analysis
+    hits: Vec<SinkHit>,
     pub(crate) aliases: HashMap<String, String>, // Hashmap for aliases check
+    // `&mut [u8]`s to part of a byte buffer, by key (see `write_byte_buffer`)
+    pub(crate) byte_views: HashMap<String, ByteView>,
 
     // registry of “interesting” callees → handler
     handlers: IndexMap<String, (CallHandler<'tcx, 'mir, 'ctx>, Vec<SinkInformation>)>,
+    // handlers of the functions the crate annotated, for calls to exactly those functions
+    annotated_handlers: HashMap<DefId, (CallHandler<'tcx, 'mir, 'ctx>, Vec<SinkInformation>)>,
     pub(crate) tcx: TyCtxt<'tcx>,
+
+    // Functions being analyzed, from the entry point down to this body (see `step_into`)
+    call_stack: Vec<DefId>,
+    // States this body returned in, kept when `collect_returns` is set
+    pub(crate) returned: Vec<SymExec<'ctx>>,
+    pub(crate) collect_returns: bool,
+    // The call being handled never returns: every path through the callee ends otherwise
+    diverges: bool,
+    // Summaries of the functions of other crates, applied at calls into them
+    pub summaries: Rc<Summaries>,
+
+    // The body is an instance (`--mono`): calls are resolved to the function they call
+    pub mono: bool,
+    // Model integer locals and constants symbolically (`--numeric`)
+    pub numeric: bool,
+    // Give integers the width and signedness of their type (`--bit-precise`)
+    pub bit_precise: bool,
+    // Print every path and the basic blocks it visits (`--action trace`)
+    pub trace: bool,
 }
 
 impl<'tcx, 'mir, 'ctx> MIRParser<'tcx, 'mir, 'ctx>
@@ -69,11 +263,22 @@ where
             mir_body: body,
             curr: z3,
             handlers: IndexMap::default(),
+            annotated_handlers: HashMap::new(),
             stack: Vec::new(),
             path_count: 0,
-            visit_counts: HashMap::new(),
+            intervals: Intervals::analyze(tcx, body),
             aliases: HashMap::new(),
-            dangerous_spans: HashMap::default(),
+            byte_views: HashMap::new(),
+            hits: Vec::new(),
+            call_stack: vec![body.source.def_id()],
+            returned: Vec::new(),
+            collect_returns: false,
+            diverges: false,
+            summaries: Rc::default(),
+            mono: false,
+            numeric: true,
+            bit_precise: false,
+            trace: false,
         };
 
         // built-ins we always want
@@ -81,11 +286,90 @@ where
         p
     }
 
-    pub(crate) fn record_sink_hit(&mut self, func_path: &str, arg: &str, span: Span) {
-        self.dangerous_spans
-            .entry((func_path.to_string(), arg.to_string()))
-            .or_default()
-            .push(span);
+    /// Records a hit of `sink` at `span`, whose checked argument `arg` has the value `value`
+    pub(crate) fn record_sink_hit(
+        &mut self,
+        func_path: &str,
+        sink: SinkInformation,
+        span: Span,
+        match_kind: MatchKind,
+        arg: &Operand<'tcx>,
+        value: &z3::ast::String<'ctx>,
+    ) {
+        let tainted = self.operand_tainted(arg);
+        let tier = match match_kind {
+            MatchKind::Always => Tier::Definite,
+            MatchKind::Possible if self.operand_data_tainted(arg) => Tier::TaintedPossible,
+            MatchKind::Possible if tainted => Tier::ImplicitFlowOnly,
+            MatchKind::Possible => Tier::UntaintedPossible,
+        };
+        let witness = self.witness(value, sink.arg_idx, sink.forbidden_val);
+        let origin = self.operand_origin(arg);
+        let steps = origin.as_deref().map(TaintStep::steps).unwrap_or_default();
+        let flow = steps
+            .iter()
+            .map(|step| FlowStep {
+                location: span_location(self.tcx, step.span),
+                message: step.message.clone(),
+            })
+            .collect();
+        let finding = Finding {
+            sink: func_path.to_string(),
+            arg_idx: sink.arg_idx,
+            forbidden: sink.forbidden_val.to_string(),
+            function: self.def_path_str(self.mir_body.source.def_id()),
+            location: span_location(self.tcx, span),
+            tainted,
+            match_kind,
+            tier,
+            flow,
+            call_chain: Vec::new(),
+            witness,
+        };
+        self.add_hit(SinkHit {
+            span,
+            finding,
+            call_spans: Vec::new(),
+            flow_spans: steps.iter().map(|step| step.span).collect(),
+        });
+    }
+
+    /// Values of the inputs that make `arg`, argument `arg_idx` of a sink, match `forbidden`:
+    /// the argument, then the named variables of this body whose value is an input the argument
+    /// or the path depends on. Empty when the solver finds no such values.
+    pub(crate) fn witness(
+        &self,
+        arg: &z3::ast::String<'ctx>,
+        arg_idx: usize,
+        forbidden: &str,
+    ) -> Vec<Witness> {
+        let cond = self.curr.matches_pattern(arg, forbidden);
+        let arg = z3::ast::Dynamic::from_ast(arg);
+        let depends_on = self.curr.inputs_of(&arg);
+        let mut exprs = vec![(format!("argument {arg_idx}"), arg)];
+        for local in self.mir_body.local_decls.indices() {
+            let Some(name) = self.local_name(local) else {
+                continue;
+            };
+            let place = Place::from(local);
+            if let Some(value) = self.curr.input_value(&self.place_key(&place))
+                && depends_on.contains(&value)
+                && !exprs.iter().any(|(n, _)| *n == name)
+            {
+                exprs.push((name, value));
+            }
+        }
+        self.curr
+            .model_values(&cond, &exprs)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(input, value)| Witness { input, value })
+            .collect()
+    }
+
+    fn add_hit(&mut self, new: SinkHit) {
+        // The same call is usually reached on several paths, keep one hit for all of them
+        merge_hit(&mut self.hits, new);
     }
 
     pub fn register_handler<S: Into<String>>(
@@ -117,19 +401,33 @@ where
     }
 
     fn add_builtin_handlers(&mut self) {
-        // register sinks from the settings
-        for (path, arg_idx, forbidden) in SINK_FUNCTION_ARGS {
-            self.register_forbid(*path, generic_string_handler, *arg_idx, forbidden);
+        // register sinks from the settings, including the env's we want to check for update
+        for (path, arg_idx, forbidden) in settings().sink_patterns() {
+            self.register_forbid(path, generic_string_handler, arg_idx, forbidden);
         }
 
-        // register env's we want to check for update
-        for &name in ENV_VARS_TO_TRACK {
-            self.register_forbid("std::env::set_var", generic_string_handler, 0, name);
+        //register sources
+        for name in &settings().sources {
+            self.register_handler(name.as_str(), handle_generic_source);
         }
 
-        //register sources
-        for &name in SOURCE_FUNCTIONS {
-            self.register_handler(name, handle_generic_source);
+        // and those the crate annotated its own functions as
+        let annotations = annotations(self.tcx);
+        for (def_id, arg_idx, forbidden) in &annotations.sinks {
+            let entry = self
+                .annotated_handlers
+                .entry(*def_id)
+                .or_insert((generic_string_handler, Vec::new()));
+            entry.1.push(SinkInformation {
+                arg_idx: *arg_idx,
+                forbidden_val: forbidden.as_str(),
+            });
+        }
+        for def_id in &annotations.sources {
+            self.annotated_handlers.insert(*def_id, (handle_generic_source, Vec::new()));
+        }
+        for def_id in &annotations.sanitizers {
+            self.annotated_handlers.insert(*def_id, (handle_sanitizer, Vec::new()));
         }
 
         // all other handlers we added for processing
@@ -163,6 +461,39 @@ where
         );
         self.register_handler("std::string::String::from_utf8", handle_string_from_utf8);
         self.register_handler("alloc::string::String::from_utf8", handle_string_from_utf8);
+        self.register_handler("std::str::from_utf8", handle_string_from_utf8);
+        self.register_handler("core::str::from_utf8", handle_string_from_utf8);
+        self.register_handler("core::str::<impl str>::as_bytes", handle_as_bytes);
+        self.register_handler("std::string::String::as_bytes", handle_as_bytes);
+        self.register_handler("std::string::String::into_bytes", handle_as_bytes);
+
+        // byte buffers
+        self.register_handler("std::vec::Vec::<T>::new", handle_bytes_new);
+        self.register_handler("std::vec::Vec::<T>::with_capacity", handle_bytes_new);
+        self.register_handler("std::vec::from_elem", handle_vec_from_elem);
+        self.register_handler("std::slice::<impl [T]>::to_vec", handle_bytes_copy);
+        self.register_handler(
+            "std::vec::Vec::<T, A>::extend_from_slice",
+            handle_vec_extend_from_slice,
+        );
+        self.register_handler("std::vec::Vec::<T, A>::push", handle_vec_extend_from_slice);
+        self.register_handler("core::slice::<impl [T]>::copy_from_slice", handle_copy_from_slice);
+        self.register_handler("std::ops::Index::index", handle_bytes_index);
+        self.register_handler("std::ops::IndexMut::index_mut", handle_bytes_index);
+        self.register_handler("std::cmp::PartialEq::eq", handle_compare);
+        self.register_handler("std::cmp::PartialEq::ne", handle_compare);
+        self.register_handler("core::slice::<impl [T]>::starts_with", handle_compare);
+        self.register_handler("core::slice::<impl [T]>::ends_with", handle_compare);
+
+        // the id of the process, `/proc/<pid>` is `/proc/self`
+        self.register_handler("std::process::id", handle_process_id);
+        self.register_handler("libc::getpid", handle_process_id);
+        self.register_handler("libc::unix::getpid", handle_process_id);
+
+        // values that come out of a `Result`, or are copied into a `String`
+        self.register_handler("std::result::Result::<T, E>::unwrap", handle_unwrap);
+        self.register_handler("std::result::Result::<T, E>::expect", handle_unwrap);
+        self.register_handler("std::string::ToString::to_string", handle_unwrap);
         self.register_handler(
             "std::result::Result::unwrap_or_default",
             handle_result_unwrap_or_default,
@@ -190,32 +521,100 @@ where
         self.register_handler("std::fmt::format", handle_fmt_format);
     }
 
+    /// The last step of how the taint of `op` got there, when it is known
+    pub(crate) fn operand_origin(&self, op: &Operand<'tcx>) -> Option<Rc<TaintStep>> {
+        let (Operand::Copy(p) | Operand::Move(p)) = op else {
+            return None;
+        };
+        let key = self.place_key(p);
+        self.curr
+            .taint_origin(&key)
+            .or_else(|| self.curr.taint_origin(&self.resolve_alias(&key)))
+    }
+
+    /// Taints `dest` when `from`, an argument of `call`, is tainted, with the call as the
+    /// last step of its flow
+    pub(crate) fn taint_through_call(&mut self, call: &Call<'tcx>, from: &Operand<'tcx>, dest: &str) {
+        if !self.operand_tainted(from) {
+            return;
+        }
+        match call.span {
+            Some(span) => {
+                let path = self.def_path_str(call.func_def_id);
+                let message = format!("it flows through this call to `{path}`");
+                let origin = self.operand_origin(from);
+                self.curr.taint_with_step(dest, span, message, origin);
+            }
+            None => self.curr.set_taint(dest, true),
+        }
+    }
+
     pub(crate) fn operand_tainted(&self, op: &Operand<'tcx>) -> bool {
         // If the path is tainted, everything is considered tainted
-        if self.curr.path_taint {
-            return true;
-        }
+        self.curr.path_taint || self.operand_data_tainted(op)
+    }
+
+    /// Whether `op` itself depends on a source, regardless of the path it is on
+    pub(crate) fn operand_data_tainted(&self, op: &Operand<'tcx>) -> bool {
         match op {
             Operand::Copy(p) | Operand::Move(p) => self.curr.is_tainted(&self.place_key(p)),
             Operand::Constant(_) => false,
         }
     }
 
-    // Main entry point: analyze the MIR and return all dangerous write locations
-    pub fn parse(&mut self) -> HashMap<(String, String), Vec<Span>> {
+    // Main entry point: analyze the MIR and return all sink hits
+    pub fn parse(&mut self) -> Vec<SinkHit> {
+        if self.bit_precise {
+            self.type_int_locals();
+        }
         self.stack
             .push((self.curr.clone(), BasicBlock::from_usize(0)));
 
+        let mut path_started = false;
         while let Some((state, bb)) = self.stack.pop() {
             self.curr = state;
-            if let Some(is_terminal) = self.parse_bb_iterative(bb) {
-                if is_terminal {
-                    self.path_count += 1;
+            if self.trace {
+                if !path_started {
+                    println!("START: Path {}!", self.path_count);
+                    path_started = true;
                 }
+                println!("\t{bb:?}");
+            }
+            if let Some(is_terminal) = self.parse_bb_iterative(bb)
+                && is_terminal
+            {
+                self.path_count += 1;
+                path_started = false;
+            }
+        }
+
+        self.hits.clone()
+    }
+
+    // The integer locals of the body that are not given a value start out as any value of
+    // their type, not any integer
+    fn type_int_locals(&mut self) {
+        for (local, local_decl) in self.mir_body.local_decls.iter_enumerated() {
+            let key = local.as_usize().to_string();
+            if let Some(ty) = self.int_ty(local_decl.ty)
+                && self.curr.input_value(&key).is_some_and(|v| v.as_int().is_some())
+            {
+                let value = self.curr.fresh_int_of(&key, ty);
+                self.curr.assign_int(&key, value);
             }
         }
+    }
 
-        self.dangerous_spans.clone()
+    // Width and signedness of `ty` when it is an integer type and `--bit-precise` is on
+    fn int_ty(&self, ty: Ty<'tcx>) -> Option<IntTy> {
+        if !self.bit_precise || !ty.is_integral() {
+            return None;
+        }
+        let (size, signed) = ty.int_size_and_signed(self.tcx);
+        Some(IntTy {
+            bits: size.bits() as u32,
+            signed,
+        })
     }
 
     pub(crate) fn def_path_str(&self, def_id: DefId) -> String {
@@ -232,8 +631,8 @@ where
                 Deref => key.push('*'),
                 Field(f, _) => key.push_str(&format!(".f{}", f.as_usize())),
                 Index(l) => key.push_str(&format!("[{}]", l.as_usize())),
-                ConstantIndex { offset, .. } => key.push_str(&format!("[{}]", offset)),
-                Subslice { from, to, .. } => key.push_str(&format!("[{}..{}]", from, to)),
+                ConstantIndex { offset, .. } => key.push_str(&format!("[{offset}]")),
+                Subslice { from, to, .. } => key.push_str(&format!("[{from}..{to}]")),
                 Downcast(_, v) => key.push_str(&format!("::variant{}", v.as_usize())),
                 OpaqueCast(_) => key.push_str("::opaque"),
                 ProjectionElem::Subtype(_) => key.push_str("::sub"),
@@ -243,46 +642,128 @@ where
         key
     }
 
-    // Collect all variables written in a basic block (for widening)
-    fn collect_written_vars(&self, bb: BasicBlock) -> HashSet<String> {
-        let mut vars = HashSet::new();
-        for stmt in &self.mir_body.basic_blocks[bb].statements {
-            if let StatementKind::Assign(assignment) = &stmt.kind {
-                vars.insert(self.place_key(&assignment.0));
+    // Gives the `locals` a loop changes new values: any value, within the intervals of the
+    // pre-pass for integers and booleans. Their taint stays as it is.
+    fn havoc_loop(&mut self, locals: &[Local]) {
+        let changed: HashSet<String> = locals.iter().map(|l| l.as_usize().to_string()).collect();
+        // the key of a place starts with its local, `_5.f0` is "5.f0"
+        let in_loop = |key: &String| {
+            let local = key.split(|c: char| !c.is_ascii_digit()).next().unwrap_or(key);
+            changed.contains(local)
+        };
+        fn keys<T>(vars: &HashMap<String, T>, in_loop: impl Fn(&String) -> bool) -> Vec<String> {
+            vars.keys().filter(|k| in_loop(k)).cloned().collect()
+        }
+        let ctx = self.curr.context;
+
+        for key in keys(&self.curr.string_variables, in_loop) {
+            let value = z3::ast::String::fresh_const(ctx, &key);
+            self.curr.assign_string(&key, value);
+        }
+        for key in keys(&self.curr.bool_variables, in_loop) {
+            let value = match self.curr.int_interval(&key).as_constant() {
+                Some(b) => self.curr.static_bool(b != 0),
+                None => z3::ast::Bool::fresh_const(ctx, &key),
+            };
+            self.curr.assign_bool(&key, value);
+        }
+        for key in keys(&self.curr.int_variables, in_loop) {
+            let ty = key
+                .parse::<usize>()
+                .ok()
+                .and_then(|l| self.int_ty(self.mir_body.local_decls[Local::from_usize(l)].ty));
+            let value = match ty {
+                Some(ty) => self.curr.fresh_int_of(&key, ty),
+                None => z3::ast::Int::fresh_const(ctx, &key),
+            };
+            let interval = self.curr.int_interval(&key);
+            // under `--bit-precise` a negative number is held as its bits
+            if !self.bit_precise || interval.lo >= Some(0) {
+                if let Some(lo) = interval.lo {
+                    let c = self.curr.int_ge(&value, &self.curr.static_int(lo));
+                    self.curr.add_constraint(c);
+                }
+                if let Some(hi) = interval.hi {
+                    let c = self.curr.int_le(&value, &self.curr.static_int(hi));
+                    self.curr.add_constraint(c);
+                }
             }
+            self.curr.assign_int(&key, value);
         }
-        vars
     }
 
-    // Check if a constraint mentions any of the given variable names
-    fn constraint_mentions(names: &HashSet<String>, constraint: &z3::ast::Bool<'ctx>) -> bool {
-        let constraint_text = constraint.to_string();
-        names.iter().any(|name| constraint_text.contains(name))
+    // Gives `place` and the places within it new values, of which nothing is known. The
+    // result of a call comes from the states it can end in, `outcomes` (a state and the key of
+    // the result in it): `place` gets a value of each sort one of them has. Taint stays as it
+    // is.
+    fn havoc_place(&mut self, place: Place<'tcx>, outcomes: &[(&SymExec<'ctx>, &str)]) {
+        let key = self.place_key(&place);
+        let ctx = self.curr.context;
+        for (state, result) in outcomes {
+            if state.get_string(result).is_some() && self.curr.get_string(&key).is_none() {
+                self.curr.assign_string(&key, z3::ast::String::fresh_const(ctx, &key));
+            }
+            if state.get_int(result).is_some() && self.curr.get_int(&key).is_none() {
+                self.curr.assign_int(&key, z3::ast::Int::fresh_const(ctx, &key));
+            }
+            if state.get_bool(result).is_some() && self.curr.get_bool(&key).is_none() {
+                self.curr.assign_bool(&key, z3::ast::Bool::fresh_const(ctx, &key));
+            }
+        }
+        // the keys of `_5.f0` and `*_5` are within that of `_5`, that of `_50` is not
+        let within = |k: &String| {
+            k.strip_prefix(key.as_str())
+                .is_some_and(|rest| !rest.starts_with(|c: char| c.is_ascii_digit()))
+        };
+        let strings: Vec<String> =
+            self.curr.string_variables.keys().filter(|k| within(k)).cloned().collect();
+        for k in strings {
+            self.curr.assign_string(&k, z3::ast::String::fresh_const(ctx, &k));
+        }
+        let bools: Vec<String> =
+            self.curr.bool_variables.keys().filter(|k| within(k)).cloned().collect();
+        for k in bools {
+            self.curr.assign_bool(&k, z3::ast::Bool::fresh_const(ctx, &k));
+        }
+        let ints: Vec<String> =
+            self.curr.int_variables.keys().filter(|k| within(k)).cloned().collect();
+        let ty = self.int_ty(place.ty(self.mir_body, self.tcx).ty);
+        for k in ints {
+            let value = match ty {
+                Some(ty) if k == key => self.curr.fresh_int_of(&k, ty),
+                _ => z3::ast::Int::fresh_const(ctx, &k),
+            };
+            self.curr.assign_int(&k, value);
+        }
     }
 
     // Process a single basic block iteratively
     fn parse_bb_iterative(&mut self, bb: BasicBlock) -> Option<bool> {
-        // Handle loops: track visit counts and apply widening
-        let counter = self.visit_counts.entry(bb).or_insert(0);
-        *counter += 1;
-
-        if *counter > MAX_LOOP_ITER {
-            return None; // Stop processing this path
+        // Handle loops: count the rounds of this path through each loop, then stand for the
+        // iterations we do not unroll
+        let max_loop_iter = settings().max_loop_iter;
+        let mut last_visit = false;
+        if self.intervals.loop_blocks(bb).is_some() {
+            // the loops inside this one start over on each of its rounds
+            for inner in self.intervals.inner_loops(bb) {
+                self.curr.loop_visits.remove(&inner);
+            }
+            let counter = self.curr.loop_visits.entry(bb).or_insert(0);
+            *counter += 1;
+            if *counter > max_loop_iter {
+                return None; // Stop processing this path
+            }
+            last_visit = *counter == max_loop_iter;
         }
 
-        if *counter == MAX_LOOP_ITER {
-            // println!(
-            //     "bb{} exceeded limit {} — applying widening",
-            //     bb.as_u32(),
-            //     MAX_LOOP_ITER
-            // );
+        if let Some(intervals) = self.intervals.at_entry(bb) {
+            self.curr.interval_map = intervals.clone();
+        }
 
-            // Widening: remove constraints on variables modified in this block
-            let written_vars = self.collect_written_vars(bb);
-            self.curr
-                .constraints
-                .retain(|c| !Self::constraint_mentions(&written_vars, c));
-            return None;
+        if last_visit && let Some(locals) = self.intervals.loop_locals(self.mir_body, bb) {
+            // This path goes on for every later round of the loop starting at `bb`: what the
+            // loop changes can have any value the pre-pass allows there
+            self.havoc_loop(&locals);
         }
 
         let data = &self.mir_body.basic_blocks[bb];
@@ -290,7 +771,7 @@ where
         // Process all statements in this basic block
         for stmt in &data.statements {
             if let StatementKind::Assign(assignment) = &stmt.kind {
-                self.parse_assignment(assignment.clone());
+                self.parse_assignment(assignment, stmt.source_info.span);
             }
         }
 
@@ -306,13 +787,13 @@ where
         );
 
         // Handle control flow based on terminator type
-        self.handle_terminator(&data.terminator().kind);
+        self.handle_terminator(bb, &data.terminator().kind);
 
         Some(is_terminal)
     }
 
     // Handle different types of control flow terminators
-    fn handle_terminator(&mut self, terminator: &TerminatorKind<'tcx>) {
+    fn handle_terminator(&mut self, bb: BasicBlock, terminator: &TerminatorKind<'tcx>) {
         match terminator {
             // Simple jump to another block
             TerminatorKind::Goto { target } => {
@@ -326,12 +807,15 @@ where
             | TerminatorKind::UnwindResume
             | TerminatorKind::UnwindTerminate { .. }
             | TerminatorKind::TailCall { .. } => {
-                // Nothing to do - this path ends
+                // Nothing to do - this path ends, the caller needs to know how when we are a callee
+                if matches!(terminator, TerminatorKind::Return) && self.collect_returns {
+                    self.returned.push(self.curr.clone());
+                }
             }
 
             // Conditional branches (if/match statements)
             TerminatorKind::SwitchInt { discr, targets } => {
-                self.handle_switch_int(discr.clone(), targets.clone());
+                self.handle_switch_int(bb, discr.clone(), targets.clone());
             }
 
             // Function calls - the most important case for our analysis
@@ -343,13 +827,7 @@ where
                 unwind,
                 ..
             } => {
-                self.handle_function_call(
-                    func.clone(),
-                    args.clone(),
-                    destination.clone(),
-                    *target,
-                    (*unwind).clone(),
-                );
+                self.handle_function_call(func.clone(), args, *destination, *target, *unwind);
             }
 
             // Runtime assertions
@@ -360,7 +838,7 @@ where
                 unwind,
                 ..
             } => {
-                self.handle_assert(cond.clone(), *expected, *target, (*unwind).clone());
+                self.handle_assert(bb, cond.clone(), *expected, *target, *unwind);
             }
 
             // Other control flow constructs
@@ -401,8 +879,8 @@ where
 
     // Parse assignment statements: `destination = rvalue`
     // This is expanded to handle more assignment types beyond just Use and BinaryOp
-    fn parse_assignment(&mut self, assignment: Box<(Place<'tcx>, Rvalue<'tcx>)>) {
-        let (destination, rvalue) = *assignment;
+    fn parse_assignment(&mut self, assignment: &(Place<'tcx>, Rvalue<'tcx>), span: Span) {
+        let (destination, rvalue) = assignment.clone();
         let dest_key = self.place_key(&destination);
 
         match rvalue {
@@ -422,21 +900,37 @@ where
             }
 
             // Type casts: `x = y as T` - needed for various type conversions
-            Rvalue::Cast(_, operand, _) => {
-                self.handle_cast_operation(&dest_key, &operand);
+            Rvalue::Cast(_, operand, ty) => {
+                self.handle_cast_operation(&dest_key, &operand, ty);
             }
 
             // Struct/tuple/array construction: `x = SomeStruct { field: value }`
             // This is crucial for tracking PathBuf construction
-            Rvalue::Aggregate(_, operands) => {
+            Rvalue::Aggregate(kind, operands) => {
+                if let AggregateKind::Closure(def_id, _)
+                | AggregateKind::Coroutine(def_id, _)
+                | AggregateKind::CoroutineClosure(def_id, _) = *kind
+                {
+                    self.handle_closure_creation(&dest_key, def_id, &operands.raw, span);
+                    return;
+                }
                 // For single-operand aggregates (like PathBuf wrapping a string), copy the value
-                if operands.len() == 1 {
-                    if let Some((_, operand)) = operands.iter_enumerated().next() {
-                        if let Operand::Copy(place) | Operand::Move(place) = operand {
-                            let src_key = self.place_key(&place);
-                            self.copy_variable_value(&src_key, &dest_key);
-                        }
-                    }
+                if operands.len() == 1
+                    && let Some((_, operand)) = operands.iter_enumerated().next()
+                {
+                    self.handle_use_operation(&dest_key, operand);
+                }
+            }
+
+            // Array repetition: `x = [0u8; 64]`, a byte buffer of known bytes
+            Rvalue::Repeat(operand, count) => {
+                if let Operand::Constant(constant) = &operand
+                    && is_u8(constant.ty())
+                    && let Some(byte) = constant.const_.try_to_scalar_int()
+                    && let Some(count) = count.try_to_target_usize(self.tcx)
+                {
+                    let bytes = vec![byte.to_u8(); count as usize];
+                    self.curr.assign_string(&dest_key, self.curr.static_bytes(&bytes));
                 }
             }
 
@@ -450,6 +944,27 @@ where
                 // println!("Unsupported Rvalue in assignment: {:?}", rvalue);
             }
         }
+
+        // Tainted data assigned to a variable of the source is a step of its flow, to a
+        // temporary it is not
+        if destination.projection.is_empty()
+            && let Some(name) = self.local_name(destination.local)
+            && let Some(origin) = self.curr.taint_origin(&dest_key)
+            && origin.span != span
+        {
+            let message = format!("it is assigned to `{name}` here");
+            self.curr.taint_with_step(&dest_key, span, message, Some(origin));
+        }
+    }
+
+    // The name of `local` in the source, if it is a variable there
+    fn local_name(&self, local: Local) -> Option<String> {
+        self.mir_body.var_debug_info.iter().find_map(|info| match info.value {
+            VarDebugInfoContents::Place(place) if place.local == local && place.projection.is_empty() => {
+                Some(info.name.to_string())
+            }
+            _ => None,
+        })
     }
 
     // Handle simple copy/move operations
@@ -482,28 +997,39 @@ where
             self.get_int_from_operand(lhs),
             self.get_int_from_operand(rhs),
         ) {
-            self.handle_int_binary_op(dest_key, op, &lhs_int, &rhs_int);
+            let ty = self.int_ty(lhs.ty(self.mir_body, self.tcx));
+            let rhs_ty = self.int_ty(rhs.ty(self.mir_body, self.tcx));
+            match (ty, rhs_ty) {
+                (Some(ty), Some(rhs_ty)) => {
+                    self.handle_bv_binary_op(dest_key, op, &lhs_int, &rhs_int, ty, rhs_ty)
+                }
+                _ => self.handle_int_binary_op(dest_key, op, &lhs_int, &rhs_int),
+            }
 
             if let Some(result) = self.curr.get_int(dest_key) {
             } else if let Some(result) = self.curr.get_bool(dest_key) {
             }
+            // a comparison of tainted numbers is a tainted condition
+            if self.operand_tainted(lhs) || self.operand_tainted(rhs) {
+                self.curr.set_taint(dest_key, true);
+            }
             return;
         }
 
         // Handle string comparisons
-        if matches!(op, BinOp::Eq | BinOp::Ne) {
-            if let (Some(lhs_str), Some(rhs_str)) = (
+        if matches!(op, BinOp::Eq | BinOp::Ne)
+            && let (Some(lhs_str), Some(rhs_str)) = (
                 self.get_string_from_operand(lhs),
                 self.get_string_from_operand(rhs),
-            ) {
-                let eq_result = self.curr.string_eq(&lhs_str, &rhs_str);
-                let final_result = if matches!(op, BinOp::Eq) {
-                    eq_result
-                } else {
-                    self.curr.not(&eq_result)
-                };
-                self.curr.assign_bool(dest_key, final_result);
-            }
+            )
+        {
+            let eq_result = self.curr.string_eq(&lhs_str, &rhs_str);
+            let final_result = if matches!(op, BinOp::Eq) {
+                eq_result
+            } else {
+                self.curr.not(&eq_result)
+            };
+            self.curr.assign_bool(dest_key, final_result);
         }
         if self.operand_tainted(lhs) || self.operand_tainted(rhs) {
             self.curr.set_taint(dest_key, true);
@@ -549,11 +1075,11 @@ where
                 };
 
                 // Store the arithmetic result in field 0 of the destination
-                let field0_key = format!("{}.f0", dest_key);
+                let field0_key = format!("{dest_key}.f0");
                 self.curr.assign_int(&field0_key, arithmetic_result);
 
                 // Store false (no overflow) in field 1 of the destination
-                let field1_key = format!("{}.f1", dest_key);
+                let field1_key = format!("{dest_key}.f1");
                 self.curr
                     .assign_bool(&field1_key, self.curr.static_bool(false));
             }
@@ -564,6 +1090,69 @@ where
         }
     }
 
+    // Handle integer binary operations at the width and signedness of `ty`, the type of the
+    // operands (`--bit-precise`; `rhs_ty` differs from it for shifts): results wrap around,
+    // division rounds toward zero and overflow flags are set when the result does not fit
+    fn handle_bv_binary_op(
+        &mut self,
+        dest_key: &str,
+        op: BinOp,
+        lhs: &z3::ast::Int<'ctx>,
+        rhs: &z3::ast::Int<'ctx>,
+        ty: IntTy,
+        rhs_ty: IntTy,
+    ) {
+        use BinOp::*;
+        let signed = ty.signed;
+        let cmp = |f: fn(&z3::ast::BV<'ctx>, &z3::ast::BV<'ctx>) -> z3::ast::Bool<'ctx>| {
+            self.curr.bv_cmp(lhs, rhs, ty, f)
+        };
+        let result = match op {
+            Eq => cmp(|a, b| a._eq(b)),
+            Ne => cmp(|a, b| a._eq(b).not()),
+            Lt if signed => cmp(|a, b| a.bvslt(b)),
+            Lt => cmp(|a, b| a.bvult(b)),
+            Le if signed => cmp(|a, b| a.bvsle(b)),
+            Le => cmp(|a, b| a.bvule(b)),
+            Gt if signed => cmp(|a, b| a.bvsgt(b)),
+            Gt => cmp(|a, b| a.bvugt(b)),
+            Ge if signed => cmp(|a, b| a.bvsge(b)),
+            Ge => cmp(|a, b| a.bvuge(b)),
+            _ => {
+                let arith: fn(&z3::ast::BV<'ctx>, &z3::ast::BV<'ctx>) -> z3::ast::BV<'ctx> =
+                    match op {
+                        Add | AddUnchecked | AddWithOverflow => |a, b| a.bvadd(b),
+                        Sub | SubUnchecked | SubWithOverflow => |a, b| a.bvsub(b),
+                        Mul | MulUnchecked | MulWithOverflow => |a, b| a.bvmul(b),
+                        Div if signed => |a, b| a.bvsdiv(b),
+                        Div => |a, b| a.bvudiv(b),
+                        Rem if signed => |a, b| a.bvsrem(b),
+                        Rem => |a, b| a.bvurem(b),
+                        BitAnd => |a, b| a.bvand(b),
+                        BitOr => |a, b| a.bvor(b),
+                        BitXor => |a, b| a.bvxor(b),
+                        Shl | ShlUnchecked | Shr | ShrUnchecked => {
+                            let left = matches!(op, Shl | ShlUnchecked);
+                            let shifted = self.curr.shift(lhs, rhs, ty, rhs_ty, left);
+                            self.curr.assign_int(dest_key, shifted);
+                            return;
+                        }
+                        _ => return,
+                    };
+                let value = self.curr.bv_op(lhs, rhs, ty, arith);
+                if matches!(op, AddWithOverflow | SubWithOverflow | MulWithOverflow) {
+                    let overflow = self.curr.bv_overflows(lhs, rhs, ty, arith);
+                    self.curr.assign_int(&format!("{dest_key}.f0"), value);
+                    self.curr.assign_bool(&format!("{dest_key}.f1"), overflow);
+                } else {
+                    self.curr.assign_int(dest_key, value);
+                }
+                return;
+            }
+        };
+        self.curr.assign_bool(dest_key, result);
+    }
+
     // Handle reference operations: `x = &y`
     // This is important for tracking when PathBuf objects are borrowed as &Path
     fn handle_reference_operation(&mut self, dest_key: &str, place: &Place<'tcx>) {
@@ -590,16 +1179,31 @@ where
 
     // Handle cast operations: `x = y as T`
     // Needed for various type conversions in path operations
-    fn handle_cast_operation(&mut self, dest_key: &str, operand: &Operand<'tcx>) {
+    fn handle_cast_operation(&mut self, dest_key: &str, operand: &Operand<'tcx>, ty: Ty<'tcx>) {
         if let Operand::Copy(place) | Operand::Move(place) = operand {
             let src_key = self.place_key(place);
             // copy value + taint
             self.copy_variable_value(&src_key, dest_key);
             self.curr.propagate_taint(&src_key, dest_key);
+            // an integer cast truncates, extends or reinterprets the sign
+            if let Some(from) = self.int_ty(operand.ty(self.mir_body, self.tcx))
+                && let Some(to) = self.int_ty(ty)
+                && let Some(value) = self.curr.get_int(dest_key)
+            {
+                let cast = self.curr.cast_int(value, from, to);
+                self.curr.assign_int(dest_key, cast);
+            }
 
             // preserve aliasing across the cast (to the base, not just the immediate key)
             let base = self.resolve_alias(&src_key);
             self.aliases.insert(dest_key.to_string(), base);
+        } else if let Operand::Constant(constant) = operand
+            && let Some(from) = self.int_ty(constant.ty())
+            && let Some(to) = self.int_ty(ty)
+            && let Some(value) = self.const_int(constant)
+        {
+            let cast = self.curr.cast_int(&value, from, to);
+            self.curr.assign_int(dest_key, cast);
         }
     }
     // Handle copy for dereference operations
@@ -612,6 +1216,17 @@ where
 
     // Copy a variable's value from source to destination
     fn copy_variable_value(&mut self, src_key: &str, dest_key: &str) {
+        // A reference holds the value of what it points to (see `handle_reference_operation`),
+        // so `*r` is `r` when nothing was stored under `*r` itself
+        let known = |key: &str| {
+            self.curr.get_string(key).is_some()
+                || self.curr.get_int(key).is_some()
+                || self.curr.get_bool(key).is_some()
+        };
+        let src_key = match src_key.strip_suffix('*') {
+            Some(reference) if !known(src_key) && known(reference) => reference,
+            _ => src_key,
+        };
         if let Some(string_val) = self.curr.get_string(src_key).cloned() {
             self.curr.assign_string(dest_key, string_val);
         } else if let Some(int_val) = self.curr.get_int(src_key).cloned() {
@@ -629,20 +1244,26 @@ where
         constant: &rustc_middle::mir::ConstOperand<'tcx>,
     ) {
         let const_val = &constant.const_;
+        let operand = Operand::Constant(Box::new(*constant));
 
         // Try different constant types
-        if let Some(scalar_int) = const_val.try_to_scalar_int() {
-            let int_val = scalar_int.to_int(scalar_int.size()) as i64;
-            let z3_int = self.curr.static_int(int_val.into());
+        if self.numeric
+            && let Some(z3_int) = self.const_int(constant)
+        {
             self.curr.assign_int(dest_key, z3_int);
-        } else if let Some(bool_val) = const_val.try_to_bool() {
+        } else if constant.ty().is_bool()
+            && let Some(bool_val) = const_val.try_to_bool()
+        {
             self.curr
                 .assign_bool(dest_key, self.curr.static_bool(bool_val));
-        } else if let Some(string_val) =
-            get_operand_const_string(&Operand::Constant(Box::new(constant.clone())))
+        } else if let Some(string_val) = get_operand_const_string(&operand)
+            .or_else(|| find_in_promoted(self.tcx, &operand, get_operand_const_string))
         {
             self.curr
                 .assign_string(dest_key, self.curr.static_string(&string_val));
+        } else if let Some(bytes) = get_operand_const_bytes(self.tcx, &operand) {
+            self.curr
+                .assign_string(dest_key, self.curr.static_bytes(&bytes));
         } else {
             // println!(
             //     "    Could not assign constant to {} - unrecognized type",
@@ -652,8 +1273,13 @@ where
     }
 
     // conditional branch handling with satisfiability checking
-    // prevents exploring unsatisfiable paths
-    fn handle_switch_int(&mut self, discr: Operand<'tcx>, targets: SwitchTargets) {
+    // prevents exploring unsatisfiable paths, and those the pre-pass rules out from `from`
+    fn handle_switch_int(
+        &mut self,
+        from: BasicBlock,
+        discr: Operand<'tcx>,
+        targets: SwitchTargets,
+    ) {
         let local = match discr {
             Operand::Copy(place) | Operand::Move(place) => place.local,
             Operand::Constant(_) => return, // Can't branch on constant
@@ -681,17 +1307,45 @@ where
                 false_state.path_taint = true;
             }
             true_state.add_constraint(true_constraint);
-            if self.is_path_satisfiable(&true_state) {
+            if self.intervals.edge_feasible(from, bb0) && self.is_path_satisfiable(&true_state) {
                 self.stack.push((true_state, bb0));
             }
             // Check if the false branch is satisfiable before exploring it
             false_state.add_constraint(false_constraint);
-            if self.is_path_satisfiable(&false_state) {
+            if self.intervals.edge_feasible(from, bb_else) && self.is_path_satisfiable(&false_state)
+            {
                 self.stack.push((false_state, bb_else));
             }
+        } else if let Some(ty) = self.int_ty(discr.ty(self.mir_body, self.tcx))
+            && let Some(value) = self.get_int_from_operand(&discr)
+        {
+            // Integer switch (`--bit-precise`): each target where the value is its own, the
+            // otherwise target where it is none of them
+            let mut otherwise = self.curr.clone();
+            if self.operand_tainted(&discr) {
+                otherwise.path_taint = true;
+            }
+            for (bits, bb) in targets.iter() {
+                let case = self.curr.static_uint(bits);
+                let eq = self.curr.bv_cmp(&value, &case, ty, |a, b| a._eq(b));
+                let mut st = otherwise.clone();
+                st.add_constraint(eq.clone());
+                if self.intervals.edge_feasible(from, bb) && self.is_path_satisfiable(&st) {
+                    self.stack.push((st, bb));
+                }
+                otherwise.add_constraint(otherwise.not(&eq));
+            }
+            if self.intervals.edge_feasible(from, targets.otherwise())
+                && self.is_path_satisfiable(&otherwise)
+            {
+                self.stack.push((otherwise, targets.otherwise()));
+            }
         } else {
             // Unknown condition: explore all branches
             for (_, bb) in targets.iter() {
+                if !self.intervals.edge_feasible(from, bb) {
+                    continue;
+                }
                 let mut st = self.curr.clone();
                 if self.operand_tainted(&discr) {
                     st.path_taint = true;
@@ -702,20 +1356,24 @@ where
             if self.operand_tainted(&discr) {
                 st.path_taint = true;
             }
-            self.stack.push((self.curr.clone(), targets.otherwise()));
+            if self.intervals.edge_feasible(from, targets.otherwise()) {
+                self.stack.push((st, targets.otherwise()));
+            }
         }
     }
 
     // Enhanced runtime assertion handling with satisfiability checking
     fn handle_assert(
         &mut self,
+        from: BasicBlock,
         cond: Operand<'tcx>,
         expected: bool,
         target: BasicBlock,
         unwind: UnwindAction,
     ) {
-        if let Some(local_idx) = get_operand_local(&cond) {
-            if let Some(bool_condition) = self.curr.get_bool(&local_idx.to_string()).cloned() {
+        // the overflow flag of a checked operation is a field, `_5.1`
+        if let Operand::Copy(place) | Operand::Move(place) = &cond {
+            if let Some(bool_condition) = self.curr.get_bool(&self.place_key(place)).cloned() {
                 // Create success path with assertion constraint
                 let mut success_state = self.curr.clone();
                 let success_constraint = if expected {
@@ -729,7 +1387,9 @@ where
                 }
 
                 // Only explore the success path if it's satisfiable
-                if self.is_path_satisfiable(&success_state) {
+                if self.intervals.edge_feasible(from, target)
+                    && self.is_path_satisfiable(&success_state)
+                {
                     self.stack.push((success_state, target));
                 }
 
@@ -747,7 +1407,9 @@ where
                     }
 
                     // Only explore the failure path if it's satisfiable
-                    if self.is_path_satisfiable(&failure_state) {
+                    if self.intervals.edge_feasible(from, cleanup_bb)
+                        && self.is_path_satisfiable(&failure_state)
+                    {
                         self.stack.push((failure_state, cleanup_bb));
                     }
                 }
@@ -757,7 +1419,9 @@ where
                 if self.operand_tainted(&cond) {
                     st.path_taint = true;
                 }
-                self.stack.push((st, target));
+                if self.intervals.edge_feasible(from, target) {
+                    self.stack.push((st, target));
+                }
             }
         } else {
             // Can't analyze condition
@@ -765,14 +1429,22 @@ where
             if self.operand_tainted(&cond) {
                 st.path_taint = true;
             }
-            self.stack.push((st, target));
+            if self.intervals.edge_feasible(from, target) {
+                self.stack.push((st, target));
+            }
         }
     }
 
+    // The handler of a call to `def_id`, whose path is `path`: the one of an annotated
+    // function, or of the longest configured path that is a prefix or suffix of `path`
     fn find_handler(
         &self,
+        def_id: DefId,
         path: &str,
     ) -> Option<(CallHandler<'tcx, 'mir, 'ctx>, Vec<SinkInformation>)> {
+        if let Some((h, sinks)) = self.annotated_handlers.get(&def_id) {
+            return Some((*h, sinks.clone()));
+        }
         if let Some((h, sinks)) = self.handlers.get(path) {
             return Some((*h, sinks.clone()));
         }
@@ -787,44 +1459,61 @@ where
     fn handle_function_call(
         &mut self,
         func: Operand<'tcx>,
-        args: Box<[rustc_span::source_map::Spanned<Operand<'tcx>>]>,
+        args: &[Spanned<Operand<'tcx>>],
         dest: Place<'tcx>,
         target: Option<BasicBlock>,
         unwind: UnwindAction,
     ) {
-        if let Some(def_id) = get_operand_def_id(&func) {
+        // Whether the call was analyzed, and the blanket taint rule is not needed
+        let mut modeled = false;
+        self.diverges = false;
+        let def_id = get_operand_def_id(&func);
+        if let Some(def_id) = def_id {
             let path = self.def_path_str(def_id);
-            println!("Function call: {}", path);
-
-            if let Some((handler, sinks)) = self.find_handler(&path) {
-                let arg_vec: Vec<Operand<'tcx>> = args.iter().map(|s| s.node.clone()).collect();
-                let base_call = Call {
-                    func_def_id: def_id,
-                    args: arg_vec,
-                    dest,
-                    span: get_operand_span(&func),
-                    sink: None,
-                };
-
-                if sinks.is_empty() {
-                    handler(self, base_call);
-                } else {
-                    for s in sinks {
-                        let mut c = base_call.clone();
-                        c.sink = Some(s);
-                        handler(self, c);
-                    }
+            let instance = if self.mono { self.resolve_call(&func) } else { None };
+            // A trait method resolves to the method of the impl that is called
+            let resolved = instance.map(|i| i.def_id()).filter(|&id| id != def_id);
+            let resolved_path = resolved.map(|id| self.def_path_str(id));
+            if self.trace {
+                match &resolved_path {
+                    Some(resolved_path) => println!("\tcall {path} ({resolved_path})"),
+                    None => println!("\tcall {path}"),
+                }
+            }
+            // Handlers of the impl's method go first, then those of the trait method
+            let handler = match (resolved, &resolved_path) {
+                (Some(id), Some(resolved_path)) => {
+                    self.find_handler(id, resolved_path).map(|(h, sinks)| (id, h, sinks))
                 }
+                _ => None,
+            }
+            .or_else(|| self.find_handler(def_id, &path).map(|(h, sinks)| (def_id, h, sinks)));
+            let callee = resolved.unwrap_or(def_id);
+
+            let span = get_operand_span(&func);
+            if let Some((handler_def_id, handler, sinks)) = handler {
+                self.run_handler(handler_def_id, handler, sinks, args, dest, span);
+                // what a sanitizer returns is clean, whatever it was given
+                modeled = annotations(self.tcx).sanitizers.contains(&handler_def_id);
+            } else if instance.is_none()
+                && let Some(candidates) = self.dyn_candidates(&func)
+            {
+                self.call_candidates(&candidates, args, dest, span);
+                modeled = true;
+            } else {
+                modeled = self.model_callee(callee, instance, def_id, args, dest, span);
             }
         }
 
-        // taint propagation
-        let dest_key = self.place_key(&dest);
-        if args.iter().any(|sp| self.operand_tainted(&sp.node)) {
-            self.curr.set_taint(&dest_key, true);
+        // taint propagation, unless we know what the callee does
+        if !modeled {
+            self.taint_dest_from_args(args, dest, def_id, get_operand_span(&func));
         }
 
-        // control flow
+        // control flow, this path ends with a callee that never returns
+        if self.diverges {
+            return;
+        }
         if let Some(next) = target {
             self.stack.push((self.curr.clone(), next));
         } else if let UnwindAction::Cleanup(clean) = unwind {
@@ -832,6 +1521,390 @@ where
         }
     }
 
+    fn run_handler(
+        &mut self,
+        func_def_id: DefId,
+        handler: CallHandler<'tcx, 'mir, 'ctx>,
+        sinks: Vec<SinkInformation>,
+        args: &[Spanned<Operand<'tcx>>],
+        dest: Place<'tcx>,
+        span: Option<Span>,
+    ) {
+        let arg_vec: Vec<Operand<'tcx>> = args.iter().map(|s| s.node.clone()).collect();
+        let base_call = Call {
+            func_def_id,
+            args: arg_vec,
+            dest,
+            span,
+            sink: None,
+        };
+
+        if sinks.is_empty() {
+            handler(self, base_call);
+        } else {
+            for s in sinks {
+                let mut c = base_call.clone();
+                c.sink = Some(s);
+                handler(self, c);
+            }
+        }
+    }
+
+    // Analyzes a call to `callee` without a handler: by stepping into it, or through the
+    // summary of it (or of `def_id`, the function the call names). Returns whether it could.
+    fn model_callee(
+        &mut self,
+        callee: DefId,
+        instance: Option<Instance<'tcx>>,
+        def_id: DefId,
+        args: &[Spanned<Operand<'tcx>>],
+        dest: Place<'tcx>,
+        span: Option<Span>,
+    ) -> bool {
+        let Some(span) = span else {
+            return false;
+        };
+        let path = self.def_path_str(callee);
+        if self.can_step_into(callee) {
+            self.step_into(callee, instance, &path, args, dest, span);
+            return true;
+        }
+        let summary = [callee, def_id]
+            .iter()
+            .find_map(|&id| self.summaries.get(&summary_key(self.tcx, id)))
+            .cloned();
+        if let Some(summary) = summary {
+            self.apply_summary(&path, &summary, args, dest, span);
+            return true;
+        }
+        false
+    }
+
+    // Blanket rule for calls we know nothing about: a tainted argument taints the result
+    fn taint_dest_from_args(
+        &mut self,
+        args: &[Spanned<Operand<'tcx>>],
+        dest: Place<'tcx>,
+        callee: Option<DefId>,
+        span: Option<Span>,
+    ) {
+        if !args.iter().any(|sp| self.operand_tainted(&sp.node)) {
+            return;
+        }
+        let dest_key = self.place_key(&dest);
+        match (callee, span) {
+            (Some(callee), Some(span)) => {
+                let message = format!("it flows through this call to `{}`", self.def_path_str(callee));
+                let origin = args.iter().find_map(|sp| self.operand_origin(&sp.node));
+                self.curr.taint_with_step(&dest_key, span, message, origin);
+            }
+            _ => self.curr.set_taint(&dest_key, true),
+        }
+    }
+
+    // The methods a call on a trait object can run (see `hierarchy`), `None` for other calls
+    fn dyn_candidates(&self, func: &Operand<'tcx>) -> Option<Vec<DefId>> {
+        let TyKind::FnDef(def_id, args) = *func.ty(self.mir_body, self.tcx).kind() else {
+            return None;
+        };
+        let self_ty = args.types().next()?;
+        if !matches!(self_ty.kind(), TyKind::Dynamic(..)) {
+            return None;
+        }
+        let candidates = implementations(self.tcx, def_id);
+        (!candidates.is_empty()).then_some(candidates)
+    }
+
+    // Runs every candidate of a call on a trait object from the current state, each the way a
+    // direct call to it would run, and keeps the taint any of them leaves behind. Values are
+    // left as they were, they differ from one candidate to the next, but the result is new.
+    // The call never returns when none of them does.
+    fn call_candidates(
+        &mut self,
+        candidates: &[DefId],
+        args: &[Spanned<Operand<'tcx>>],
+        dest: Place<'tcx>,
+        span: Option<Span>,
+    ) {
+        let start = self.curr.clone();
+        let mut ends = Vec::new();
+        for &candidate in candidates {
+            let path = self.def_path_str(candidate);
+            if self.trace {
+                println!("\t  candidate {path}");
+            }
+            if let Some((handler, sinks)) = self.find_handler(candidate, &path) {
+                self.run_handler(candidate, handler, sinks, args, dest, span);
+                // what a sanitizer returns is clean, whatever it was given
+                if !annotations(self.tcx).sanitizers.contains(&candidate) {
+                    self.taint_dest_from_args(args, dest, Some(candidate), span);
+                }
+            } else if !self.model_callee(candidate, None, candidate, args, dest, span) {
+                self.taint_dest_from_args(args, dest, Some(candidate), span);
+            }
+            let end = std::mem::replace(&mut self.curr, start.clone());
+            if !std::mem::take(&mut self.diverges) {
+                ends.push(end);
+            }
+        }
+        self.diverges = ends.is_empty();
+        for end in &ends {
+            self.curr.join_taint(end);
+        }
+        let dest_key = self.place_key(&dest);
+        let outcomes: Vec<_> = ends.iter().map(|end| (end, dest_key.as_str())).collect();
+        self.havoc_place(dest, &outcomes);
+    }
+
+    // The function an instance's call actually calls: generic functions get the concrete
+    // arguments of the call, and trait methods resolve to the impl's method. Virtual calls,
+    // shims and intrinsics are left alone.
+    fn resolve_call(&self, func: &Operand<'tcx>) -> Option<Instance<'tcx>> {
+        let TyKind::FnDef(def_id, args) = *func.ty(self.mir_body, self.tcx).kind() else {
+            return None;
+        };
+        if args.has_param() {
+            return None;
+        }
+        let instance =
+            Instance::try_resolve(self.tcx, TypingEnv::fully_monomorphized(), def_id, args)
+                .ok()??;
+        matches!(instance.def, InstanceKind::Item(_)).then_some(instance)
+    }
+
+    // Calls into the analyzed crate are followed, up to `max_call_depth` calls deep and
+    // without recursion. Closures and the like are left to the blanket taint rule.
+    fn can_step_into(&self, def_id: DefId) -> bool {
+        def_id.is_local()
+            && matches!(self.tcx.def_kind(def_id), DefKind::Fn | DefKind::AssocFn)
+            && self.can_enter(def_id)
+    }
+
+    fn can_enter(&self, def_id: DefId) -> bool {
+        self.tcx.is_mir_available(def_id)
+            && self.call_stack.len() <= settings().max_call_depth as usize
+            && !self.call_stack.contains(&def_id)
+    }
+
+    // Analyzes the body of `def_id` (of `instance` when the call was resolved) with the
+    // arguments of this call, under the constraints of the current path. Its sink hits get
+    // this call prepended to their call chain, and its return value and what it writes
+    // through its arguments come back to this path.
+    fn step_into(
+        &mut self,
+        def_id: DefId,
+        instance: Option<Instance<'tcx>>,
+        path: &str,
+        args: &[Spanned<Operand<'tcx>>],
+        dest: Place<'tcx>,
+        span: Span,
+    ) {
+        let body = match instance {
+            Some(instance) => instance_body(self.tcx, instance),
+            None => self.tcx.optimized_mir(def_id),
+        };
+        // Arguments are locals 1..=n, a reference argument is read through `*`
+        let inputs: Vec<(String, &Operand<'tcx>)> = args
+            .iter()
+            .enumerate()
+            .flat_map(|(i, arg)| {
+                let local = (i + 1).to_string();
+                [(format!("{local}*"), &arg.node), (local, &arg.node)]
+            })
+            .collect();
+        let step = FlowStep {
+            location: span_location(self.tcx, span),
+            message: format!("call to `{path}`"),
+        };
+        let returned = self.enter_body(def_id, body, instance.is_some(), &inputs, step, span);
+        if returned.is_empty() {
+            self.diverges = true;
+            return;
+        }
+
+        let dest_key = self.place_key(&dest);
+        // With a single way out of the callee, its result and constraints are exact
+        if let [state] = returned.as_slice() {
+            if let Some(s) = state.get_string("0") {
+                self.curr.assign_string(&dest_key, s.clone());
+            } else if let Some(n) = state.get_int("0") {
+                self.curr.assign_int(&dest_key, n.clone());
+            } else if let Some(b) = state.get_bool("0") {
+                self.curr.assign_bool(&dest_key, b.clone());
+            }
+            self.curr.constraints = state.constraints.clone();
+        } else {
+            // the result depends on the way out of the callee
+            let outcomes: Vec<_> = returned.iter().map(|state| (state, "0")).collect();
+            self.havoc_place(dest, &outcomes);
+        }
+        let message = format!("it flows out of this call to `{path}`");
+        let origin = |key: &str| returned.iter().find_map(|state| state.taint_origin(key));
+        if returned.iter().any(|state| state.is_tainted("0")) {
+            self.curr.taint_with_step(&dest_key, span, message.clone(), origin("0"));
+        }
+        for (i, arg) in args.iter().enumerate() {
+            let written = format!("{}*", i + 1);
+            if let Operand::Copy(place) | Operand::Move(place) = &arg.node
+                && returned.iter().any(|state| state.is_tainted(&written))
+            {
+                let key = self.place_key(place);
+                let base = self.resolve_alias(&key);
+                self.curr.taint_with_step(&key, span, message.clone(), origin(&written));
+                self.curr.taint_with_step(&base, span, message.clone(), origin(&written));
+            }
+        }
+    }
+
+    // Analyzes `body` (of `def_id`) as called from the current path: under its constraints,
+    // with each key of `inputs` holding the value and taint of its operand. The hits get
+    // `step` prepended to their call chain, and the states the body returned in come back.
+    fn enter_body(
+        &mut self,
+        def_id: DefId,
+        body: &'tcx Body<'tcx>,
+        mono: bool,
+        inputs: &[(String, &Operand<'tcx>)],
+        step: FlowStep,
+        span: Span,
+    ) -> Vec<SymExec<'ctx>> {
+        let mut ev = SymExec::new(self.curr.context);
+        declare_locals(&mut ev, body, self.numeric);
+        ev.freshen();
+        ev.constraints = self.curr.constraints.clone();
+        ev.solver = self.curr.solver.clone();
+        ev.path_taint = self.curr.path_taint;
+        for (key, op) in inputs {
+            if let Some(s) = self.get_string_from_operand(op) {
+                ev.assign_string(key, s);
+            } else if let Some(n) = self.get_int_from_operand(op) {
+                ev.assign_int(key, n);
+            } else if let Operand::Copy(place) | Operand::Move(place) = op
+                && let Some(b) = self.curr.get_bool(&self.place_key(place))
+            {
+                ev.assign_bool(key, b.clone());
+            }
+            if self.operand_tainted(op) {
+                ev.set_taint(key, true);
+                if let Some(origin) = self.operand_origin(op) {
+                    ev.taint_origins.insert(key.clone(), origin);
+                }
+            }
+        }
+
+        let mut callee = MIRParser::new(self.tcx, body, ev);
+        callee.numeric = self.numeric;
+        callee.bit_precise = self.bit_precise;
+        callee.call_stack = self.call_stack.clone();
+        callee.call_stack.push(def_id);
+        callee.collect_returns = true;
+        callee.summaries = self.summaries.clone();
+        callee.mono = mono;
+        for mut hit in callee.parse() {
+            hit.call_spans.insert(0, span);
+            hit.finding.call_chain.insert(0, step.clone());
+            self.add_hit(hit);
+        }
+        callee.returned
+    }
+
+    // A closure or coroutine (the body of an `async fn` or block) is analyzed where it is
+    // created, with its captures as they are on this path: it runs later, maybe on another
+    // thread, but everything it gets from this body is in its captures
+    fn handle_closure_creation(
+        &mut self,
+        dest_key: &str,
+        def_id: DefId,
+        upvars: &[Operand<'tcx>],
+        span: Span,
+    ) {
+        if upvars.iter().any(|op| self.operand_tainted(op)) {
+            self.curr.set_taint(dest_key, true);
+        }
+        if !self.can_enter(def_id) {
+            return;
+        }
+        let body = self.tcx.optimized_mir(def_id);
+        let inputs: Vec<(String, &Operand<'tcx>)> = env_keys(body, def_id)
+            .iter()
+            .flat_map(|env| {
+                upvars.iter().enumerate().flat_map(move |(i, op)| {
+                    [(format!("{env}.f{i}"), op), (format!("{env}.f{i}*"), op)]
+                })
+            })
+            .collect();
+        // the header of the closure, rather than all of it
+        let span = self.tcx.def_span(def_id);
+        let step = FlowStep {
+            location: span_location(self.tcx, span),
+            message: format!("closure `{}`", self.def_path_str(def_id)),
+        };
+        self.enter_body(def_id, body, false, &inputs, step, span);
+    }
+
+    // A call into another crate, analyzed through the summary that crate wrote: the sinks
+    // that the tainted arguments reach are hits at this call, pointing at the sink call
+    fn apply_summary(
+        &mut self,
+        path: &str,
+        summary: &FunctionSummary,
+        args: &[Spanned<Operand<'tcx>>],
+        dest: Place<'tcx>,
+        span: Span,
+    ) {
+        let tainted: Vec<bool> = args.iter().map(|a| self.operand_tainted(&a.node)).collect();
+        let is_tainted = |arg: &usize| tainted.get(*arg).copied().unwrap_or(false);
+        for arg_sink in summary.sinks.iter().filter(|s| is_tainted(&s.arg)) {
+            let steps = args
+                .get(arg_sink.arg)
+                .and_then(|a| self.operand_origin(&a.node))
+                .map(|origin| origin.steps().iter().map(|s| (s.span, s.message.clone())).collect())
+                .unwrap_or_else(Vec::new);
+            let tier = match args.get(arg_sink.arg) {
+                Some(a) if self.operand_data_tainted(&a.node) => Tier::TaintedPossible,
+                _ => Tier::ImplicitFlowOnly,
+            };
+            let finding = Finding {
+                sink: arg_sink.sink.clone(),
+                arg_idx: arg_sink.sink_arg,
+                forbidden: arg_sink.forbidden.clone(),
+                function: self.def_path_str(self.mir_body.source.def_id()),
+                location: span_location(self.tcx, span),
+                tainted: true,
+                match_kind: MatchKind::Possible,
+                tier,
+                flow: steps
+                    .iter()
+                    .map(|(span, message)| FlowStep {
+                        location: span_location(self.tcx, *span),
+                        message: message.clone(),
+                    })
+                    .collect(),
+                call_chain: vec![FlowStep {
+                    location: arg_sink.location.clone(),
+                    message: format!("call to `{}` in `{path}`", arg_sink.sink),
+                }],
+                witness: Vec::new(),
+            };
+            // the sink call is in another crate, the diagnostic can only point at this call
+            self.add_hit(SinkHit {
+                span,
+                finding,
+                call_spans: Vec::new(),
+                flow_spans: steps.iter().map(|(span, _)| *span).collect(),
+            });
+        }
+        let dest_key = self.place_key(&dest);
+        if summary.returns_tainted {
+            let message = format!("tainted data comes from this call to `{path}`");
+            self.curr.taint_with_step(&dest_key, span, message, None);
+        } else if let Some(&arg) = summary.tainted_return_from.iter().find(|a| is_tainted(a)) {
+            let message = format!("it flows out of this call to `{path}`");
+            let origin = args.get(arg).and_then(|a| self.operand_origin(&a.node));
+            self.curr.taint_with_step(&dest_key, span, message, origin);
+        }
+    }
+
     // Hassnain : Removed this function, as we are using a generic string matching fucniton now
     // fn check_write_safety(&self, path_operand: &Operand<'tcx>) -> bool {
 
@@ -846,39 +1919,88 @@ where
                 let base = self.resolve_alias(&key);
                 self.curr.get_string(&base).cloned()
             }
-            Operand::Constant(_) => {
-                get_operand_const_string(operand).map(|s| self.curr.static_string(&s))
-            }
+            Operand::Constant(_) => get_operand_const_string(operand)
+                .or_else(|| find_in_promoted(self.tcx, operand, get_operand_const_string))
+                .map(|s| self.curr.static_string(&s))
+                .or_else(|| {
+                    get_operand_const_bytes(self.tcx, operand).map(|b| self.curr.static_bytes(&b))
+                }),
         }
     }
 
+    /// Writes the bytes `value` into the byte buffer `key` refers to, or into the part of one
+    /// when `key` is a view of it (see `ByteView`). The buffer is left unknown when the part
+    /// cannot be told apart from the rest.
+    pub(crate) fn write_byte_buffer(&mut self, key: &str, value: z3::ast::String<'ctx>) {
+        let Some(view) = self.byte_views.get(key).cloned() else {
+            let base = self.resolve_alias(key);
+            self.curr.assign_string(&base, value.clone());
+            self.curr.assign_string(key, value);
+            return;
+        };
+        let old = self.curr.get_string(&view.base).cloned();
+        let new = old
+            .as_ref()
+            .zip(view.range)
+            .and_then(|(old, (start, end))| self.curr.write_bytes(old, start, end, &value))
+            .unwrap_or_else(|| {
+                let max_len = old.as_ref().and_then(|old| self.curr.bytes_len(old));
+                self.curr.fresh_bytes(&view.base, max_len)
+            });
+        self.curr.assign_string(&view.base, new);
+        self.curr.assign_string(key, value);
+    }
+
+    /// The byte buffer `key` refers to, the one it is a view of for a `ByteView`
+    pub(crate) fn byte_buffer_base(&self, key: &str) -> String {
+        match self.byte_views.get(key) {
+            Some(view) => view.base.clone(),
+            None => self.resolve_alias(key),
+        }
+    }
+
+    /// The value of `operand` when it is a known `usize` (`--numeric`)
+    pub(crate) fn get_usize_from_operand(&self, operand: &Operand<'tcx>) -> Option<usize> {
+        let value = self.get_int_from_operand(operand)?.simplify().as_u64()?;
+        usize::try_from(value).ok()
+    }
+
     // Extract integer value from an operand
     // Helper function for binary operations
-    fn get_int_from_operand(&self, operand: &Operand<'tcx>) -> Option<z3::ast::Int<'ctx>> {
+    pub(crate) fn get_int_from_operand(&self, operand: &Operand<'tcx>) -> Option<z3::ast::Int<'ctx>> {
+        if !self.numeric {
+            return None;
+        }
         match operand {
             Operand::Copy(place) | Operand::Move(place) => {
                 let key = self.place_key(place);
                 self.curr.get_int(&key).cloned()
             }
-            Operand::Constant(c) => c
-                .const_
-                .try_to_scalar_int()
-                .map(|si| self.curr.static_int((si.to_int(si.size()) as i64).into())),
+            Operand::Constant(c) => self.const_int(c),
         }
     }
 
+    // The value of an integer constant: its bits under `--bit-precise` (see
+    // `SymExec::from_bv`), a signed 64-bit number otherwise
+    fn const_int(&self, constant: &ConstOperand<'tcx>) -> Option<z3::ast::Int<'ctx>> {
+        let si = if self.bit_precise {
+            // named constants, `u32::MAX`, are evaluated too
+            let typing_env = self.mir_body.typing_env(self.tcx);
+            constant.const_.try_eval_scalar_int(self.tcx, typing_env)?
+        } else {
+            constant.const_.try_to_scalar_int()?
+        };
+        Some(match self.int_ty(constant.ty()) {
+            Some(_) => self.curr.static_uint(si.to_uint(si.size())),
+            None => self.curr.static_int((si.to_int(si.size()) as i64).into()),
+        })
+    }
+
     // Check if a given execution state has satisfiable constraints
     fn is_path_satisfiable(&self, state: &SymExec<'ctx>) -> bool {
-        // Create a temporary solver to check satisfiability
-        let solver = z3::Solver::new(&state.context);
-
-        // Add all constraints from the state
-        for constraint in &state.constraints {
-            solver.assert(constraint);
-        }
-
-        // Check if the constraints are satisfiable
-        match solver.check() {
+        // Check if the constraints are satisfiable, asserting only those the solver shared with
+        // the other states does not hold yet
+        match state.check_sat() {
             SatResult::Sat => {
                 // Path is satisfiable - we can explore it
                 true
@@ -915,3 +2037,130 @@ pub struct Call<'tcx> {
 }
 
 type CallHandler<'tcx, 'mir, 'ctx> = fn(&mut MIRParser<'tcx, 'mir, 'ctx>, Call<'tcx>);
+
+#[test]
+fn test_bool_constants() {
+    // Without `--numeric`, integer constants are left unread, and only bool constants are
+    // assigned as booleans, so the branch on `enabled` is decided by its value
+    let source = r#"
+        pub fn disabled() {
+            let count: i32 = 7;
+            let enabled = false;
+            if enabled {
+                let _ = std::fs::write("/proc/self/mem", count.to_string());
+            }
+            if enabled {
+                println!("wrote {count} bytes");
+            }
+        }
+
+        pub fn enabled() {
+            let count: i32 = 7;
+            let enabled = true;
+            if enabled {
+                let _ = std::fs::write("/proc/self/mem", count.to_string());
+            }
+            if enabled {
+                println!("wrote {count} bytes");
+            }
+        }
+    "#;
+    crate::callback::with_tcx("bool_constants", source, &[], |tcx| {
+        for local_def_id in tcx.hir_body_owners() {
+            let cfg = z3::Config::new();
+            let ctx = z3::Context::new(&cfg);
+            let body = tcx.optimized_mir(local_def_id);
+            let mut parser = MIRParser::new(tcx, body, SymExec::new(&ctx));
+            parser.numeric = false;
+            let hits = parser.parse();
+            let name = tcx.def_path_str(local_def_id);
+            assert_eq!(hits.is_empty(), name == "disabled", "{name}");
+        }
+    });
+}
+
+#[test]
+fn test_annotated_handlers() {
+    // The local `read_to_string` is a sink, `std::fs::read_to_string` is not
+    let source = r#"
+        #![feature(register_tool)]
+        #![register_tool(lhs)]
+
+        #[lhs::sink(arg = 0, forbid = "/proc/self/mem")]
+        pub fn read_to_string(path: &str) -> String {
+            path.to_string()
+        }
+
+        pub fn local() -> String {
+            read_to_string("/proc/self/mem")
+        }
+
+        pub fn std() -> String {
+            std::fs::read_to_string("/proc/self/mem").unwrap_or_default()
+        }
+    "#;
+    let args = crate::cli::Args::default();
+    let findings = crate::callback::analyze_source("annotated_handlers", source, args);
+    let functions: Vec<&str> = findings.iter().map(|f| f.function.as_str()).collect();
+    assert_eq!(functions, ["local"]);
+}
+
+#[test]
+fn test_witness_guard() {
+    // The witness passes the guard in front of the sink
+    let source = r#"
+        #![feature(register_tool)]
+        #![register_tool(lhs)]
+
+        #[lhs::source]
+        pub fn input() -> String {
+            String::new()
+        }
+
+        pub fn borrowed(contents: &str) {
+            let owned = input();
+            let filename = owned.as_str();
+            if filename == "/proc/self/mem" {
+                return;
+            }
+            std::fs::write(filename, contents).unwrap();
+        }
+
+        pub fn owned(contents: &str) {
+            let filename = input();
+            if filename != "/proc/self/mem" {
+                std::fs::write(&filename, contents).unwrap();
+            }
+        }
+    "#;
+    let args = crate::cli::Args::default();
+    let findings = crate::callback::analyze_source("witness_guard", source, args);
+    assert_eq!(findings.len(), 2);
+    for finding in &findings {
+        let filename = finding.witness.iter().find(|w| w.input == "filename").unwrap();
+        assert_ne!(filename.value, "\"/proc/self/mem\"", "{}", finding.function);
+    }
+}
+
+#[test]
+fn test_diverging_callee() {
+    // Nothing after a call to a function that always panics runs
+    let source = r#"
+        fn refuse(path: &str) {
+            panic!("refusing to write {path}");
+        }
+
+        pub fn refused() {
+            refuse("/proc/self/mem");
+            std::fs::write("/proc/self/mem", "").unwrap();
+        }
+
+        pub fn written() {
+            std::fs::write("/proc/self/mem", "").unwrap();
+        }
+    "#;
+    let args = crate::cli::Args::default();
+    let findings = crate::callback::analyze_source("diverging_callee", source, args);
+    let functions: Vec<&str> = findings.iter().map(|f| f.function.as_str()).collect();
+    assert_eq!(functions, ["written"]);
+}
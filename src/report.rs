@@ -0,0 +1,345 @@
+//!
+//! Findings reported by the analysis and the JSON report they are written to
+//!
+//! This module does not depend on `rustc_private`: `cargo lhs` includes it to read the
+//! reports back, and so can any other tool that wants to consume them.
+//!
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Version of the report schema below. Bump it whenever a field changes meaning or is
+/// removed; adding a field does not need a bump.
+pub const REPORT_VERSION: u32 = 1;
+
+/// Why the forbidden value can reach the sink
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum MatchKind {
+    /// The argument is the forbidden value on every execution of the path (e.g. a constant)
+    Always,
+    /// The argument is tainted and can be the forbidden value on some executions
+    Possible,
+}
+
+/// How strong the evidence for a finding is, strongest first
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Tier {
+    /// The argument is the forbidden value on every execution of the path
+    Definite,
+    /// Data from a source reaches the argument, which can be the forbidden value
+    #[default]
+    TaintedPossible,
+    /// The argument can be the forbidden value but does not depend on a source, e.g. an
+    /// unconstrained parameter
+    UntaintedPossible,
+    /// The argument can be the forbidden value on a path a source decided, but does not
+    /// depend on a source itself
+    ImplicitFlowOnly,
+}
+
+impl Tier {
+    pub const ALL: [Tier; 4] = [
+        Tier::Definite,
+        Tier::TaintedPossible,
+        Tier::UntaintedPossible,
+        Tier::ImplicitFlowOnly,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Tier::Definite => "definite",
+            Tier::TaintedPossible => "tainted-possible",
+            Tier::UntaintedPossible => "untainted-possible",
+            Tier::ImplicitFlowOnly => "implicit-flow-only",
+        }
+    }
+}
+
+impl std::str::FromStr for Tier {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Tier::ALL.into_iter().find(|t| t.name() == s).ok_or_else(|| {
+            let names: Vec<&str> = Tier::ALL.iter().map(|t| t.name()).collect();
+            format!("unknown tier `{s}`, expected one of {}", names.join(", "))
+        })
+    }
+}
+
+impl std::fmt::Display for Tier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Source location of a finding. Lines and columns start at 1, `*_hi` is exclusive.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Location {
+    pub file: String,
+    pub line_lo: usize,
+    pub col_lo: usize,
+    pub line_hi: usize,
+    pub col_hi: usize,
+}
+
+/// One call to a sink whose argument can be a forbidden value
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// Path of the sink function, as configured in the settings
+    pub sink: String,
+    /// Index of the checked argument of the sink
+    pub arg_idx: usize,
+    /// The forbidden value or pattern the argument can match
+    pub forbidden: String,
+    /// Path of the function containing the call
+    pub function: String,
+    pub location: Location,
+    /// Whether the argument is derived from a source
+    pub tainted: bool,
+    pub match_kind: MatchKind,
+    /// Reports written before tiers existed read as `tainted-possible`
+    #[serde(default)]
+    pub tier: Tier,
+    /// How the value got from its source to the sink, when that is known
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flow: Vec<FlowStep>,
+    /// The calls into local functions that lead from the analyzed function to `function`,
+    /// outermost first. Empty when the sink is called by the analyzed function itself.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub call_chain: Vec<FlowStep>,
+    /// Values that make the argument the forbidden value, from the solver's model: the argument
+    /// itself, then the parameters and the data read from sources it depends on
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub witness: Vec<Witness>,
+}
+
+/// The value of one input in a finding's witness
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Witness {
+    /// `argument <i>` for the argument of the sink, the name of the variable otherwise
+    pub input: String,
+    /// The value, as a Rust literal (`"/proc/self/mem"`, `42`, `true`)
+    pub value: String,
+}
+
+/// One step of a finding's flow or call chain, in execution order
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FlowStep {
+    pub location: Location,
+    pub message: String,
+}
+
+/// Everything found in one crate
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub version: u32,
+    pub crate_name: String,
+    pub findings: Vec<Finding>,
+}
+
+impl Report {
+    pub fn new(crate_name: String, findings: Vec<Finding>) -> Self {
+        Report {
+            version: REPORT_VERSION,
+            crate_name,
+            findings,
+        }
+    }
+}
+
+/// Name of a crate's report in the output directory. There is one per crate and target
+/// (`hash` tells e.g. the lib and test builds of a crate apart): `foo-lib-1a2b3c4d5e6f7a8b.json`
+pub fn report_file_name(crate_name: &str, kind: &str, hash: u64) -> String {
+    format!("{crate_name}-{kind}-{hash:016x}.json")
+}
+
+/// Whether `name` is the name of a report (see `report_file_name`). The output directory can
+/// hold other files, those are left alone.
+pub fn is_report_file_name(name: &str) -> bool {
+    let Some((rest, hash)) = name.strip_suffix(".json").and_then(|n| n.rsplit_once('-')) else {
+        return false;
+    };
+    // crate names have no `-`, crate types like `proc-macro` do
+    let Some((crate_name, kind)) = rest.split_once('-') else {
+        return false;
+    };
+    hash.len() == 16
+        && hash.chars().all(|c| c.is_ascii_hexdigit())
+        && !crate_name.is_empty()
+        && crate_name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && !kind.is_empty()
+        && kind.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '+')
+}
+
+/// Writes `report` to `dir/name` atomically. Cargo runs rustc processes in parallel, each
+/// writes its own file through a temporary one, so readers never see a partial report.
+pub fn write_report(dir: &Path, name: &str, report: &Report) -> io::Result<()> {
+    write_atomic(dir, name, &serde_json::to_vec_pretty(report)?)
+}
+
+/// Writes `contents` to `dir/name` through a temporary file, creating `dir` if needed
+pub fn write_atomic(dir: &Path, name: &str, contents: &[u8]) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let tmp = dir.join(format!(".{name}.{}.tmp", std::process::id()));
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, dir.join(name))
+}
+
+/// Reads every report in `dir` (other files are skipped) and combines the ones of the same
+/// crate (built for several targets) into one, without duplicate findings. Reports are sorted
+/// by crate name.
+pub fn merge_reports(dir: &Path) -> io::Result<Vec<Report>> {
+    let mut paths: Vec<_> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|n| is_report_file_name(&n.to_string_lossy()))
+        })
+        .collect();
+    paths.sort();
+
+    let mut merged: BTreeMap<String, Report> = BTreeMap::new();
+    for path in paths {
+        let report: Report = serde_json::from_slice(&fs::read(&path)?).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {e}", path.display()),
+            )
+        })?;
+        let entry = merged
+            .entry(report.crate_name.clone())
+            .or_insert_with(|| Report::new(report.crate_name.clone(), Vec::new()));
+        for finding in report.findings {
+            if !entry.findings.contains(&finding) {
+                entry.findings.push(finding);
+            }
+        }
+    }
+    Ok(merged.into_values().collect())
+}
+
+/// The findings of `reports` as `function,value,index,span` rows, where `function` is the sink,
+/// `value` the forbidden value, and `index` counts the rows of each (sink, value) pair.
+pub fn to_csv(reports: &[Report]) -> String {
+    let mut csv = String::from("function,value,index,span\n");
+    let mut counts: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+    for finding in reports.iter().flat_map(|r| &r.findings) {
+        let i = counts
+            .entry((&finding.sink, &finding.forbidden))
+            .or_default();
+        *i += 1;
+        let l = &finding.location;
+        csv.push_str(&format!(
+            "{},{},{},{}:{}:{}: {}:{}\n",
+            finding.sink, finding.forbidden, i, l.file, l.line_lo, l.col_lo, l.line_hi, l.col_hi
+        ));
+    }
+    csv
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line_lo, self.col_lo)
+    }
+}
+
+#[test]
+fn test_report_schema() {
+    let report = Report::new(
+        "ex1".into(),
+        vec![Finding {
+            sink: "std::fs::write".into(),
+            arg_idx: 0,
+            forbidden: "/proc/self/mem".into(),
+            function: "write_to_file".into(),
+            location: Location {
+                file: "src/lib.rs".into(),
+                line_lo: 5,
+                col_lo: 5,
+                line_hi: 5,
+                col_hi: 14,
+            },
+            tainted: true,
+            match_kind: MatchKind::Possible,
+            tier: Tier::TaintedPossible,
+            flow: Vec::new(),
+            call_chain: Vec::new(),
+            witness: vec![Witness {
+                input: "argument 0".into(),
+                value: "\"/proc/self/mem\"".into(),
+            }],
+        }],
+    );
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["version"], REPORT_VERSION);
+    assert_eq!(json["findings"][0]["match_kind"], "possible");
+    assert_eq!(json["findings"][0]["tier"], "tainted-possible");
+    assert_eq!(json["findings"][0]["location"]["line_lo"], 5);
+    assert_eq!(json["findings"][0]["witness"][0]["value"], "\"/proc/self/mem\"");
+    assert_eq!(serde_json::from_value::<Report>(json).unwrap(), report);
+}
+
+#[test]
+fn test_report_file_name() {
+    let name = report_file_name("my_crate", "proc-macro", 0x1a2b3c4d5e6f7a8b);
+    assert!(is_report_file_name(&name));
+    assert!(is_report_file_name("foo-lib+rlib-00000000000000ff.json"));
+    assert!(!is_report_file_name("package.json"));
+    assert!(!is_report_file_name("tsconfig.json"));
+    assert!(!is_report_file_name("foo-lib-1a2b.json"));
+    assert!(!is_report_file_name(&format!(".{name}.42.tmp")));
+}
+
+#[test]
+fn test_merge_reports() {
+    let finding = |function: &str| Finding {
+        sink: "std::fs::write".into(),
+        arg_idx: 0,
+        forbidden: "/proc/self/mem".into(),
+        function: function.into(),
+        location: Location {
+            file: "src/lib.rs".into(),
+            line_lo: 5,
+            col_lo: 5,
+            line_hi: 5,
+            col_hi: 14,
+        },
+        tainted: true,
+        match_kind: MatchKind::Possible,
+        tier: Tier::TaintedPossible,
+        flow: Vec::new(),
+        call_chain: Vec::new(),
+        witness: Vec::new(),
+    };
+    let dir = std::env::temp_dir().join(format!("lhs-merge-reports-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    // the lib and test builds of `foo` both find `write_to_file`
+    let lib = Report::new("foo".into(), vec![finding("write_to_file")]);
+    let test = Report::new("foo".into(), vec![finding("write_to_file"), finding("tests::it")]);
+    let bar = Report::new("bar".into(), Vec::new());
+    write_report(&dir, &report_file_name("foo", "lib", 1), &lib).unwrap();
+    write_report(&dir, &report_file_name("foo", "lib", 2), &test).unwrap();
+    write_report(&dir, &report_file_name("bar", "bin", 3), &bar).unwrap();
+    // a report another build is still writing, and a file that is no report at all
+    let name = report_file_name("foo", "lib", 4);
+    fs::write(dir.join(format!(".{name}.42.tmp")), "{\"version\":").unwrap();
+    fs::write(dir.join("package.json"), "{}").unwrap();
+
+    let merged = merge_reports(&dir).unwrap();
+    let names: Vec<&str> = merged.iter().map(|r| r.crate_name.as_str()).collect();
+    assert_eq!(names, ["bar", "foo"]);
+    assert_eq!(merged[1].findings, [finding("write_to_file"), finding("tests::it")]);
+    // the written reports' temporary files were renamed, only the leftover one is still there
+    let tmp = fs::read_dir(&dir)
+        .unwrap()
+        .filter(|e| e.as_ref().unwrap().file_name().to_string_lossy().ends_with(".tmp"))
+        .count();
+    assert_eq!(tmp, 1);
+    fs::remove_dir_all(&dir).unwrap();
+}
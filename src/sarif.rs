@@ -0,0 +1,257 @@
+//!
+//! SARIF 2.1.0 output, for code scanning dashboards
+//!
+//! Every checked (sink, argument, forbidden value) becomes a rule and every finding a result.
+//! Like `report`, this module does not depend on `rustc_private`, so that `cargo lhs` can turn
+//! the reports of a whole build into one log.
+//!
+use serde_json::{Value, json};
+
+use crate::cli::Level;
+use crate::report::{Finding, Location, Report};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Id of the rule for a sink pattern. It only depends on the pattern itself, so results stay
+/// matched to their rule when patterns are added or removed.
+pub fn rule_id(sink: &str, arg_idx: usize, forbidden: &str) -> String {
+    format!("{sink}[{arg_idx}]={forbidden}")
+}
+
+/// Builds a SARIF log with one run over `reports`. `patterns` are the configured sinks (see
+/// `Settings::sink_patterns`), and `level` gives the level each sink is reported at.
+pub fn sarif_log(
+    reports: &[Report],
+    patterns: &[(&str, usize, &str)],
+    level: impl Fn(&str) -> Level,
+) -> Value {
+    let mut rule_ids: Vec<String> = Vec::new();
+    let mut rules = Vec::new();
+    let mut add_rule = |sink: &str, arg_idx: usize, forbidden: &str| -> usize {
+        let id = rule_id(sink, arg_idx, forbidden);
+        if let Some(index) = rule_ids.iter().position(|r| *r == id) {
+            return index;
+        }
+        rules.push(json!({
+            "id": id,
+            "name": "ForbiddenSinkArgument",
+            "shortDescription": {
+                "text": format!("`{forbidden}` reaches argument {arg_idx} of `{sink}`"),
+            },
+            "fullDescription": {
+                "text": format!(
+                    "Argument {arg_idx} of `{sink}` is a sink that must not receive `{forbidden}`."
+                ),
+            },
+            "defaultConfiguration": { "level": sarif_level(level(sink)) },
+        }));
+        rule_ids.push(id);
+        rule_ids.len() - 1
+    };
+
+    for &(sink, arg_idx, forbidden) in patterns {
+        add_rule(sink, arg_idx, forbidden);
+    }
+    let mut results = Vec::new();
+    for report in reports {
+        for finding in &report.findings {
+            // findings of patterns that are not configured anymore still get a rule
+            let rule_index = add_rule(&finding.sink, finding.arg_idx, &finding.forbidden);
+            results.push(sarif_result(
+                report,
+                finding,
+                rule_index,
+                level(&finding.sink),
+            ));
+        }
+    }
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "lhs",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/DavisPL/lhs",
+                    "rules": rules,
+                },
+            },
+            "originalUriBaseIds": {
+                "%SRCROOT%": { "description": { "text": "The root of the analyzed project" } },
+            },
+            "results": results,
+        }],
+    })
+}
+
+fn sarif_result(report: &Report, finding: &Finding, rule_index: usize, level: Level) -> Value {
+    let mut result = json!({
+        "ruleId": rule_id(&finding.sink, finding.arg_idx, &finding.forbidden),
+        "ruleIndex": rule_index,
+        "level": sarif_level(level),
+        "message": {
+            "text": format!("call to `{}` may receive `{}`", finding.sink, finding.forbidden),
+        },
+        "locations": [{
+            "physicalLocation": physical_location(&finding.location),
+            "logicalLocations": [{
+                "fullyQualifiedName": finding.function,
+                "kind": "function",
+            }],
+        }],
+        "properties": {
+            "crate": report.crate_name,
+            "tainted": finding.tainted,
+            "matchKind": finding.match_kind,
+            "tier": finding.tier,
+        },
+    });
+    if !finding.flow.is_empty() {
+        let steps: Vec<Value> = finding
+            .flow
+            .iter()
+            .map(|step| {
+                json!({
+                    "location": {
+                        "physicalLocation": physical_location(&step.location),
+                        "message": { "text": step.message },
+                    },
+                })
+            })
+            .collect();
+        result["codeFlows"] = json!([{ "threadFlows": [{ "locations": steps }] }]);
+    }
+    if !finding.witness.is_empty() {
+        let witness: Vec<Value> = finding
+            .witness
+            .iter()
+            .map(|w| json!({ "input": w.input, "value": w.value }))
+            .collect();
+        result["properties"]["witness"] = json!(witness);
+    }
+    if !finding.call_chain.is_empty() {
+        let calls: Vec<Value> = finding
+            .call_chain
+            .iter()
+            .enumerate()
+            .map(|(id, step)| {
+                json!({
+                    "id": id,
+                    "physicalLocation": physical_location(&step.location),
+                    "message": { "text": step.message },
+                })
+            })
+            .collect();
+        result["relatedLocations"] = json!(calls);
+    }
+    result
+}
+
+// Cargo passes the sources of the workspace relative to its root, those of other packages
+// (registry, git) as absolute paths
+fn physical_location(location: &Location) -> Value {
+    let artifact = if location.file.starts_with('/') {
+        json!({ "uri": format!("file://{}", location.file) })
+    } else {
+        json!({ "uri": location.file, "uriBaseId": "%SRCROOT%" })
+    };
+    json!({
+        "artifactLocation": artifact,
+        "region": {
+            "startLine": location.line_lo,
+            "startColumn": location.col_lo,
+            "endLine": location.line_hi,
+            "endColumn": location.col_hi,
+        },
+    })
+}
+
+fn sarif_level(level: Level) -> &'static str {
+    match level {
+        Level::Allow => "none",
+        Level::Warn => "warning",
+        Level::Deny => "error",
+    }
+}
+
+#[test]
+fn test_sarif_log() {
+    use crate::cli::Args;
+    use crate::report::{FlowStep, MatchKind, Tier};
+    use clap::Parser;
+    let location = |line| Location {
+        file: "src/main.rs".into(),
+        line_lo: line,
+        col_lo: 5,
+        line_hi: line,
+        col_hi: 14,
+    };
+    let finding = |sink: &str, forbidden: &str, line, tier| Finding {
+        sink: sink.into(),
+        arg_idx: 0,
+        forbidden: forbidden.into(),
+        function: "main".into(),
+        location: location(line),
+        tainted: true,
+        match_kind: MatchKind::Possible,
+        tier,
+        flow: vec![FlowStep {
+            location: location(3),
+            message: "read from `std::env::args`".into(),
+        }],
+        call_chain: Vec::new(),
+        witness: Vec::new(),
+    };
+    let report = Report::new(
+        "ex1".into(),
+        vec![
+            finding("std::fs::write", "/proc/self/mem", 9, Tier::TaintedPossible),
+            finding("std::fs::remove_file", "/etc/*", 10, Tier::TaintedPossible),
+            finding("std::env::set_var", "LD_PRELOAD", 11, Tier::TaintedPossible),
+            // a finding of another tier keeps the rule and level of its sink
+            finding("std::fs::write", "/proc/self/mem", 12, Tier::ImplicitFlowOnly),
+        ],
+    );
+    let patterns = [
+        ("std::fs::write", 0, "/proc/self/mem"),
+        ("std::fs::remove_file", 0, "/etc/*"),
+        ("std::env::set_var", 0, "LD_PRELOAD"),
+        ("std::fs::copy", 1, "/etc/*"),
+    ];
+    let argv = "lhs -D all -A std::fs::remove_file -W std::env::set_var";
+    let args = Args::parse_from(argv.split_whitespace());
+    let log = sarif_log(&[report], &patterns, |sink| args.sink_level(sink));
+    assert_eq!(log["version"], "2.1.0");
+    let run = &log["runs"][0];
+    let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+    assert_eq!(rules.len(), 4);
+    assert_eq!(rules[0]["id"], "std::fs::write[0]=/proc/self/mem");
+    let rule_levels: Vec<_> = rules
+        .iter()
+        .map(|rule| &rule["defaultConfiguration"]["level"])
+        .collect();
+    assert_eq!(rule_levels, ["error", "none", "warning", "error"]);
+
+    let results = run["results"].as_array().unwrap();
+    let levels: Vec<_> = results.iter().map(|result| &result["level"]).collect();
+    assert_eq!(levels, ["error", "none", "warning", "error"]);
+    let tiers: Vec<_> = results.iter().map(|result| &result["properties"]["tier"]).collect();
+    assert_eq!(
+        tiers,
+        ["tainted-possible", "tainted-possible", "tainted-possible", "implicit-flow-only"]
+    );
+    assert_eq!(results[3]["ruleIndex"], 0);
+    let result = &results[0];
+    assert_eq!(result["ruleId"], rules[0]["id"]);
+    assert_eq!(result["ruleIndex"], 0);
+    assert_eq!(results[2]["ruleIndex"], 2);
+    let physical = &result["locations"][0]["physicalLocation"];
+    assert_eq!(physical["artifactLocation"]["uri"], "src/main.rs");
+    assert_eq!(physical["artifactLocation"]["uriBaseId"], "%SRCROOT%");
+    assert_eq!(physical["region"]["startLine"], 9);
+    let step = &result["codeFlows"][0]["threadFlows"][0]["locations"][0]["location"];
+    assert_eq!(step["physicalLocation"]["region"]["startLine"], 3);
+    assert_eq!(step["message"]["text"], "read from `std::env::args`");
+}
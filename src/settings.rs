@@ -1,9 +1,20 @@
 //!
 //! Settings and constants to configure the analysis
 //!
+//! The constants are the defaults. A `lhs.toml` file (see `settings.toml` in this repository
+//! for the schema) overrides them at runtime; it is looked up from `--config`, then the
+//! `LHS_CONFIG` environment variable, then the root of the analyzed project.
 //!
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::report::Tier;
+
 pub const MAX_LOOP_ITER: u32 = 5; // Maximum loop iterations before widening
 
+pub const MAX_CALL_DEPTH: u32 = 3; // How many calls deep the analysis follows local functions
+
 pub const ENV_VARS_TO_TRACK: &[&str] = &["RUSTC", "CARGO"]; // env's we want to track/protect against
 
 pub const SOURCE_FUNCTIONS: &[&str] = &[
@@ -26,3 +37,181 @@ pub const SINK_FUNCTION_ARGS: &[(&str, usize, &str)] = &[
     ("std::env::set_var", 0, "RUSTC"),
     ("std::process::Command::new", 0, "rm -rf *"),
 ];
+
+/// Finding tiers that are reported: every one but `untainted-possible`, which flags any sink
+/// whose argument is unknown
+pub const REPORTED_TIERS: &[Tier] = &[
+    Tier::Definite,
+    Tier::TaintedPossible,
+    Tier::ImplicitFlowOnly,
+];
+
+/// Name of the configuration file looked up in the analyzed project
+pub const CONFIG_FILE_NAME: &str = "lhs.toml";
+
+/// Environment variable pointing at a configuration file
+pub const CONFIG_ENV: &str = "LHS_CONFIG";
+
+/// The analysis configuration. A key present in the file replaces the default.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Maximum loop iterations before widening
+    pub max_loop_iter: u32,
+    /// How many calls deep the analysis steps into local functions, 0 to never step into them
+    pub max_call_depth: u32,
+    /// Environment variables `std::env::set_var` must not overwrite
+    pub env_vars_to_track: Vec<String>,
+    /// Functions whose results are tainted
+    pub sources: Vec<String>,
+    pub sinks: Vec<Sink>,
+    /// Finding tiers that are reported, `--tier` overrides them
+    pub tiers: Vec<Tier>,
+    pub tainted_params: TaintedParams,
+}
+
+/// Functions whose parameters are tainted, as if their callers passed data from a source. This
+/// audits a library for what the callers of its API can make it do.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct TaintedParams {
+    /// The crate's `main` function
+    pub main: bool,
+    /// Every function callers outside the crate can reach
+    pub public: bool,
+    /// Functions whose path matches one of these, `*` matching any sequence of characters
+    pub functions: Vec<String>,
+}
+
+/// A call whose argument `arg` must not be `forbidden` (a value, or a pattern with `*`). An
+/// absolute path also forbids the paths that resolve to it.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Sink {
+    pub function: String,
+    #[serde(default)]
+    pub arg: usize,
+    pub forbidden: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            max_loop_iter: MAX_LOOP_ITER,
+            max_call_depth: MAX_CALL_DEPTH,
+            env_vars_to_track: ENV_VARS_TO_TRACK.iter().map(|s| s.to_string()).collect(),
+            sources: SOURCE_FUNCTIONS.iter().map(|s| s.to_string()).collect(),
+            sinks: SINK_FUNCTION_ARGS
+                .iter()
+                .map(|&(function, arg, forbidden)| Sink {
+                    function: function.to_string(),
+                    arg,
+                    forbidden: forbidden.to_string(),
+                })
+                .collect(),
+            tiers: REPORTED_TIERS.to_vec(),
+            tainted_params: TaintedParams::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn load(path: &Path) -> Result<Settings, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("could not read {}: {e}", path.display()))?;
+        toml::from_str(&text).map_err(|e| format!("invalid settings in {}: {e}", path.display()))
+    }
+
+    /// Finds and loads the configuration: `explicit` (`--config`), then `LHS_CONFIG`, then
+    /// the closest `lhs.toml` above the package cargo is building (or the current directory).
+    /// Without any, the defaults are used.
+    pub fn discover(explicit: Option<&Path>) -> Result<Settings, String> {
+        match config_path(explicit) {
+            Some(path) => Settings::load(&path),
+            None => Ok(Settings::default()),
+        }
+    }
+
+    /// Every (sink, argument, forbidden value) the analysis checks: the configured sinks, plus
+    /// `std::env::set_var` for each of the tracked env's
+    pub fn sink_patterns(&self) -> Vec<(&str, usize, &str)> {
+        let mut patterns: Vec<_> = self
+            .sinks
+            .iter()
+            .map(|s| (s.function.as_str(), s.arg, s.forbidden.as_str()))
+            .collect();
+        for name in &self.env_vars_to_track {
+            let pattern = ("std::env::set_var", 0, name.as_str());
+            if !patterns.contains(&pattern) {
+                patterns.push(pattern);
+            }
+        }
+        patterns
+    }
+}
+
+/// Whether `path` matches `pattern`, where a `*` matches any sequence of characters
+pub fn path_matches(pattern: &str, path: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // no `*`
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// The configuration file to use, if any (see `Settings::discover`)
+pub fn config_path(explicit: Option<&Path>) -> Option<PathBuf> {
+    if let Some(path) = explicit {
+        return Some(path.to_path_buf());
+    }
+    if let Some(path) = std::env::var_os(CONFIG_ENV) {
+        return Some(PathBuf::from(path));
+    }
+    let start = std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .or_else(|| std::env::current_dir().ok())?;
+    start
+        .ancestors()
+        .map(|dir| dir.join(CONFIG_FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// Installs the settings for this process, the first call wins
+pub fn init(settings: Settings) {
+    let _ = SETTINGS.set(settings);
+}
+
+/// The settings of this process, the defaults if `init` was never called
+pub fn settings() -> &'static Settings {
+    SETTINGS.get_or_init(Settings::default)
+}
+
+#[test]
+fn test_settings_toml_is_the_default() {
+    let example: Settings = toml::from_str(include_str!("../settings.toml")).unwrap();
+    assert_eq!(example, Settings::default());
+}
+
+#[test]
+fn test_path_matches() {
+    assert!(path_matches("api::write_to_file", "api::write_to_file"));
+    assert!(!path_matches("api::write", "api::write_to_file"));
+    assert!(path_matches("api::*", "api::write_to_file"));
+    assert!(path_matches("*::write_*", "api::fs::write_to_file"));
+    assert!(path_matches("*file", "api::write_to_file"));
+    assert!(!path_matches("api::*::file", "api::file"));
+}
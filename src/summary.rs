@@ -0,0 +1,100 @@
+//!
+//! Function summaries, so the crates that use a dependency can analyze calls into it
+//!
+//! Every analyzed crate writes one summary file to `summaries/` in the output directory. It
+//! records, for each public function, which arguments reach which sinks and which ones the
+//! return value is tainted by. Crates that are compiled later load the summaries of their
+//! dependencies and apply them at call sites, in place of a handler.
+//!
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::report::{Location, write_atomic};
+
+/// Version of the summary schema below, summaries of another version are ignored
+pub const SUMMARY_VERSION: u32 = 1;
+
+/// Directory the summaries are written to, inside the output directory
+pub const SUMMARY_DIR: &str = "summaries";
+
+/// The summaries of one crate
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CrateSummary {
+    pub version: u32,
+    pub crate_name: String,
+    pub stable_crate_id: u64,
+    /// Summaries by the def path hash of their function, which identifies it across crates
+    pub functions: BTreeMap<String, FunctionSummary>,
+}
+
+/// What a call to a function does with the taint of its arguments
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct FunctionSummary {
+    /// Path of the function, for humans
+    pub path: String,
+    /// Sinks a tainted argument can reach with a forbidden value
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sinks: Vec<ArgSink>,
+    /// Arguments (from 0) whose taint reaches the return value
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tainted_return_from: Vec<usize>,
+    /// Whether the return value is tainted whatever the arguments, i.e. the function is a source
+    #[serde(default)]
+    pub returns_tainted: bool,
+}
+
+/// Argument `arg` of a function reaches argument `sink_arg` of `sink` at `location`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ArgSink {
+    pub arg: usize,
+    pub sink: String,
+    pub sink_arg: usize,
+    pub forbidden: String,
+    pub location: Location,
+}
+
+/// Summaries of every loaded crate, by def path hash
+pub type Summaries = HashMap<String, FunctionSummary>;
+
+impl CrateSummary {
+    pub fn new(crate_name: String, stable_crate_id: u64) -> Self {
+        CrateSummary {
+            version: SUMMARY_VERSION,
+            crate_name,
+            stable_crate_id,
+            functions: BTreeMap::new(),
+        }
+    }
+}
+
+/// Path of the summary of a crate. Two crates of the same name in one build (e.g. two
+/// versions of a dependency) have different stable crate ids.
+pub fn summary_path(out_dir: &Path, crate_name: &str, stable_crate_id: u64) -> PathBuf {
+    out_dir
+        .join(SUMMARY_DIR)
+        .join(format!("{crate_name}-{stable_crate_id:016x}.json"))
+}
+
+/// Writes `summary` atomically, like the reports
+pub fn write_summary(out_dir: &Path, summary: &CrateSummary) -> io::Result<()> {
+    let path = summary_path(out_dir, &summary.crate_name, summary.stable_crate_id);
+    let name = path.file_name().unwrap().to_string_lossy();
+    write_atomic(
+        &out_dir.join(SUMMARY_DIR),
+        &name,
+        &serde_json::to_vec_pretty(summary)?,
+    )
+}
+
+/// Reads the summary of a crate, `None` when it was not written or by another version of LHS
+pub fn read_summary(
+    out_dir: &Path,
+    crate_name: &str,
+    stable_crate_id: u64,
+) -> Option<CrateSummary> {
+    let bytes = std::fs::read(summary_path(out_dir, crate_name, stable_crate_id)).ok()?;
+    let summary: CrateSummary = serde_json::from_slice(&bytes).ok()?;
+    (summary.version == SUMMARY_VERSION).then_some(summary)
+}
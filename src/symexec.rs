@@ -1,7 +1,21 @@
+use rustc_middle::mir::BasicBlock;
+use rustc_span::Span;
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::rc::Rc;
 use z3;
 use z3::ast::{Ast, Regexp};
 
+use crate::interval::{Interval, IntervalState};
+
+/// Names of the process id and of its digits (see `SymExecBool::process_id`)
+const PROCESS_ID: &str = "process id";
+const PROCESS_ID_DIGITS: &str = "process id digits";
+
+/// How deep the detours into a directory and back out (`dir/..`) of a path can nest for
+/// `resolves_to` to see through them
+const DETOUR_DEPTH: usize = 4;
+
 #[derive(Debug, Clone)]
 pub struct Slot<T> {
     pub value: T,
@@ -17,6 +31,172 @@ impl<T> Slot<T> {
     }
 }
 
+/// One step of how tainted data got into a variable, after the steps of the data it came from
+#[derive(Debug)]
+pub struct TaintStep {
+    pub span: Span,
+    pub message: String,
+    pub prev: Option<Rc<TaintStep>>,
+}
+
+impl TaintStep {
+    /// The steps from the source to this one, without repeating a step (loops go through the
+    /// same ones several times)
+    pub fn steps(&self) -> Vec<&TaintStep> {
+        let mut steps = Vec::new();
+        let mut step = Some(self);
+        while let Some(s) = step {
+            steps.push(s);
+            step = s.prev.as_deref();
+        }
+        steps.reverse();
+        let mut seen = Vec::new();
+        steps.retain(|s| {
+            let new = !seen.contains(&(s.span, &s.message));
+            seen.push((s.span, &s.message));
+            new
+        });
+        steps
+    }
+}
+
+/// Width and signedness of a Rust integer type, for bit-precise arithmetic (`--bit-precise`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntTy {
+    pub bits: u32,
+    pub signed: bool,
+}
+
+/// `codes` as the text of a z3 string literal. z3 reads the bytes of the text one by one, so
+/// the characters that are not printable ASCII, and `\`, are written as `\u{..}` escapes.
+fn escape(codes: impl Iterator<Item = u32>) -> String {
+    codes
+        .map(|code| match char::from_u32(code) {
+            Some(c @ ' '..='~') if c != '\\' => c.to_string(),
+            _ => format!("\\u{{{code:x}}}"),
+        })
+        .collect()
+}
+
+/// The character codes of `s` when it is a literal
+fn literal_codes(s: &z3::ast::String<'_>) -> Option<Vec<u32>> {
+    if !s.is_const() || s.safe_decl().ok()?.kind() != z3::DeclKind::INTERNAL {
+        return None;
+    }
+    let text = s.as_string()?;
+    let mut codes = Vec::new();
+    let mut rest = text.as_str();
+    while let Some(c) = rest.chars().next() {
+        if let Some(escaped) = rest.strip_prefix("\\u{")
+            && let Some((hex, after)) = escaped.split_once('}')
+            && let Ok(code) = u32::from_str_radix(hex, 16)
+        {
+            codes.push(code);
+            rest = after;
+        } else {
+            codes.push(c.into());
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    Some(codes)
+}
+
+// The number of bytes at the end of `bytes` that start a UTF-8 sequence without finishing it
+fn incomplete_tail(bytes: &[u8]) -> usize {
+    for n in 1..=bytes.len().min(3) {
+        let len = match bytes[bytes.len() - n] {
+            0x80..=0xbf => continue,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => 1,
+        };
+        return if len > n { n } else { 0 };
+    }
+    0
+}
+
+/// The strings `s` is the concatenation of, `s` itself if it is not one
+fn concat_parts<'ctx>(s: &z3::ast::String<'ctx>) -> Vec<z3::ast::String<'ctx>> {
+    if s.safe_decl().is_ok_and(|d| d.kind() == z3::DeclKind::SEQ_CONCAT) {
+        s.children()
+            .iter()
+            .filter_map(|c| c.as_string())
+            .flat_map(|c| concat_parts(&c))
+            .collect()
+    } else {
+        vec![s.clone()]
+    }
+}
+
+/// Where the bytes of a slice `&b[range]` are in `b`: at its start (`..end`), at its end
+/// (`start..`), or somewhere in it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BytesPart {
+    Prefix,
+    Suffix,
+    Inside,
+}
+
+/// A solver shared by the states of a path and the states forked from it. It keeps the
+/// constraints of the last state it checked asserted, one backtracking point each, so a state
+/// whose constraints extend those only asserts the new ones; states are explored depth first, so
+/// most of them do.
+#[derive(Debug)]
+pub struct IncrementalSolver<'ctx> {
+    solver: z3::Solver<'ctx>,
+    asserted: Vec<z3::ast::Bool<'ctx>>,
+}
+
+impl<'ctx> IncrementalSolver<'ctx> {
+    pub fn new(context: &'ctx z3::Context) -> Self {
+        Self { solver: z3::Solver::new(context), asserted: Vec::new() }
+    }
+
+    // Pops the constraints after the prefix `constraints` shares with the asserted ones, and
+    // asserts the rest of `constraints`
+    fn sync(&mut self, constraints: &[z3::ast::Bool<'ctx>]) {
+        let common = self.asserted.iter().zip(constraints).take_while(|(a, c)| a == c).count();
+        if common < self.asserted.len() {
+            self.solver.pop((self.asserted.len() - common) as u32);
+            self.asserted.truncate(common);
+        }
+        for c in &constraints[common..] {
+            self.solver.push();
+            self.solver.assert(c);
+            self.asserted.push(c.clone());
+        }
+    }
+
+    /// Checks `constraints` with `extra`, which is retracted afterwards
+    pub fn check(
+        &mut self,
+        constraints: &[z3::ast::Bool<'ctx>],
+        extra: Option<&z3::ast::Bool<'ctx>>,
+    ) -> z3::SatResult {
+        self.check_with(constraints, extra, |result, _| result)
+    }
+
+    /// Checks `constraints` with `extra` like `check`, and gives the result and the solver, from
+    /// which a model can be taken, to `f` before retracting `extra`
+    pub fn check_with<T>(
+        &mut self,
+        constraints: &[z3::ast::Bool<'ctx>],
+        extra: Option<&z3::ast::Bool<'ctx>>,
+        f: impl FnOnce(z3::SatResult, &z3::Solver<'ctx>) -> T,
+    ) -> T {
+        self.sync(constraints);
+        let Some(extra) = extra else {
+            return f(self.solver.check(), &self.solver);
+        };
+        self.solver.push();
+        self.solver.assert(extra);
+        let result = f(self.solver.check(), &self.solver);
+        self.solver.pop(1);
+        result
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SymExecBool<'ctx> {
     pub context: &'ctx z3::Context,
@@ -24,7 +204,21 @@ pub struct SymExecBool<'ctx> {
     pub int_variables: HashMap<String, Slot<z3::ast::Int<'ctx>>>,
     pub bool_variables: HashMap<String, Slot<z3::ast::Bool<'ctx>>>,
     pub constraints: Vec<z3::ast::Bool<'ctx>>,
-    pub interval_map: HashMap<String, (Option<i128>, Option<i128>)>,
+    /// The solver `constraints` are checked with, shared with the states forked from this one
+    pub solver: Rc<RefCell<IncrementalSolver<'ctx>>>,
+    /// Intervals of the integer and boolean places where the path is, from the pre-pass (see
+    /// `Intervals`)
+    pub interval_map: IntervalState,
+    /// Where the taint of tainted variables comes from, when it is known
+    pub taint_origins: HashMap<String, Rc<TaintStep>>,
+    /// Bytes cut out of a buffer at an unknown place (see `bytes_part`), by buffer, part and
+    /// bound of the range
+    pub byte_parts: HashMap<(z3::ast::String<'ctx>, BytesPart, z3::ast::Int<'ctx>), z3::ast::String<'ctx>>,
+    /// Bytes made of a single repeated byte (see `repeated_byte`), with that byte
+    pub repeated_bytes: HashMap<z3::ast::String<'ctx>, u8>,
+    /// Rounds of this path through each loop of the body, by the block the loop starts at (see
+    /// `MIRParser::parse_bb_iterative`)
+    pub loop_visits: HashMap<BasicBlock, u32>,
 
     pub path_taint: bool, // useful for cases like examples/unsafe/command2
 }
@@ -38,7 +232,12 @@ impl<'ctx> SymExecBool<'ctx> {
             int_variables: HashMap::new(),
             bool_variables: HashMap::new(),
             constraints: Vec::new(),
+            solver: Rc::new(RefCell::new(IncrementalSolver::new(context))),
             interval_map: HashMap::new(),
+            taint_origins: HashMap::new(),
+            byte_parts: HashMap::new(),
+            repeated_bytes: HashMap::new(),
+            loop_visits: HashMap::new(),
             path_taint: false,
         }
     }
@@ -78,7 +277,6 @@ impl<'ctx> SymExecBool<'ctx> {
     pub fn create_int_with_flag(&mut self, name: &str, flag: bool) {
         let v = z3::ast::Int::new_const(self.context, name);
         SymExecBool::insert_with_flag(&mut self.int_variables, name, v, flag);
-        self.interval_map.insert(name.into(), (None, None));
     }
     // Create uninterpreted integer variables without a taint flag.
     pub fn create_int(&mut self, name: &str) {
@@ -96,6 +294,20 @@ impl<'ctx> SymExecBool<'ctx> {
         self.create_uninterpreted_bool_with_flag(name, false);
     }
 
+    /// Replaces the value of every variable with a new, unconstrained one. The locals of a
+    /// callee have the same names as those of its caller, this keeps them apart in z3.
+    pub fn freshen(&mut self) {
+        for (name, slot) in self.string_variables.iter_mut() {
+            slot.value = z3::ast::String::fresh_const(self.context, name);
+        }
+        for (name, slot) in self.int_variables.iter_mut() {
+            slot.value = z3::ast::Int::fresh_const(self.context, name);
+        }
+        for (name, slot) in self.bool_variables.iter_mut() {
+            slot.value = z3::ast::Bool::fresh_const(self.context, name);
+        }
+    }
+
     /// Assigns a string with the given value to the given variable name, adding it to the executor. Can also be used to replace the value of a string variable.
     pub fn assign_string(&mut self, name: &str, value: z3::ast::String<'ctx>) {
         let f = self.string_flag(name).unwrap_or(false);
@@ -105,7 +317,6 @@ impl<'ctx> SymExecBool<'ctx> {
     pub fn assign_int(&mut self, name: &str, value: z3::ast::Int<'ctx>) {
         let f = self.int_flag(name).unwrap_or(false);
         SymExecBool::insert_with_flag(&mut self.int_variables, name, value, f);
-        self.interval_map.entry(name.into()).or_insert((None, None));
     }
     /// Assigns a boolean with the given value to the given variable name, adding it to the executor. Can also be used to replace the value of a boolean variable.
     pub fn assign_bool(&mut self, name: &str, value: z3::ast::Bool<'ctx>) {
@@ -156,41 +367,47 @@ impl<'ctx> SymExecBool<'ctx> {
     }
     /// Checks if the constraints in the executor and the new constraint are satisfiable.
     pub fn check_constraint_sat(&self, new_c: &z3::ast::Bool<'ctx>) -> z3::SatResult {
-        let s = z3::Solver::new(self.context);
-        for c in &self.constraints {
-            s.assert(c);
-        }
-        s.assert(new_c);
-        s.check()
-    }
-    /// Checks if there is an assignment to symbolic variables in the executor such that write_arg_name matches /proc/self/mem.
-    /// This function can be used to check that a write such a `fs::write(filename, contents)` does not write to the directory
-    /// /proc/self/mem. The argument write_arg_name must already be present in the environment. If it is not, an Error is returned.
-    pub fn is_write_safe(&self, expr: &z3::ast::String<'ctx>) -> Result<z3::SatResult, &str> {
-        let s = z3::Solver::new(self.context);
-        for c in &self.constraints {
-            s.assert(c);
-        }
-        let slash = Regexp::literal(self.context, "/");
-        let dot = Regexp::literal(self.context, "./");
-        let pat = &[
-            &slash,
-            &Regexp::union(self.context, &[&slash, &dot]).star(),
-            &Regexp::literal(self.context, "proc"),
-            &slash,
-            &Regexp::union(self.context, &[&slash, &dot]).star(),
-            &Regexp::literal(self.context, "self"),
-            &slash,
-            &Regexp::union(self.context, &[&slash, &dot]).star(),
-            &Regexp::literal(self.context, "mem"),
-        ];
-        s.assert(&expr.regex_matches(&Regexp::concat(self.context, pat)));
-        Ok(s.check())
+        self.solver.borrow_mut().check(&self.constraints, Some(new_c))
+    }
+    /// Checks if the constraints in the executor are satisfiable.
+    pub fn check_sat(&self) -> z3::SatResult {
+        self.solver.borrow_mut().check(&self.constraints, None)
+    }
+    /// Whether the path `expr` resolves to one matching `pattern`, an absolute path where a `*`
+    /// matches any sequence of characters. `expr` may repeat slashes, have `.` segments, go up
+    /// from the root and go into directories and back out with `..`, nested up to
+    /// `DETOUR_DEPTH` deep. `/proc/self` may also be written `/proc/thread-self` or
+    /// `/proc/<pid>` with the id of the process (see `process_id`). Symbolic links are not
+    /// followed.
+    pub fn resolves_to(&self, expr: &z3::ast::String<'ctx>, pattern: &str) -> z3::ast::Bool<'ctx> {
+        let ctx = self.context;
+        let (parts, proc_self) = self.path_parts(pattern);
+        let refs: Vec<&Regexp<'ctx>> = parts.iter().collect();
+        let path = Regexp::concat(ctx, &refs);
+        let digits = self.process_id_digits();
+        if !concat_parts(expr).contains(&digits) {
+            return expr.regex_matches(&path);
+        }
+        // The digits are a name no literal component has, unless they stand for `self`. They
+        // do only if no fork happened since the id was taken, which is never certain.
+        let name = expr.substitute(&[(&digits, &self.static_string("\0"))]);
+        let spelled = name.regex_matches(&path);
+        if !proc_self {
+            return spelled;
+        }
+        let own = expr.substitute(&[(&digits, &self.static_string("self"))]);
+        let same = z3::ast::Bool::fresh_const(ctx, "same process");
+        let by_pid = z3::ast::Bool::and(ctx, &[&own.regex_matches(&path), &same]);
+        z3::ast::Bool::or(ctx, &[&spelled, &by_pid])
     }
 
     /// Creates a z3 string expression from a Rust static string.
     pub fn static_string(&self, v: &str) -> z3::ast::String<'ctx> {
-        z3::ast::String::from_str(self.context, v).unwrap()
+        self.static_chars(v.chars().map(u32::from))
+    }
+    // A z3 string literal of the characters with the codes `codes`
+    fn static_chars(&self, codes: impl Iterator<Item = u32>) -> z3::ast::String<'ctx> {
+        z3::ast::String::from_str(self.context, &escape(codes)).unwrap()
     }
     /// Creates a z3 string expression from the concatenation of two strings.
     pub fn concat_strings(
@@ -240,6 +457,245 @@ impl<'ctx> SymExecBool<'ctx> {
     )
     }
 
+    /// Creates a z3 string of the bytes `v`, one character per byte. Byte buffers (`[u8; N]`,
+    /// `[u8]`, `Vec<u8>`) are strings like these, `decode_utf8` turns them into text.
+    pub fn static_bytes(&self, v: &[u8]) -> z3::ast::String<'ctx> {
+        self.static_chars(v.iter().map(|&b| u32::from(b)))
+    }
+    /// The bytes of `b` when they are all known
+    pub fn const_bytes(&self, b: &z3::ast::String<'ctx>) -> Option<Vec<u8>> {
+        literal_codes(b)?
+            .into_iter()
+            .map(|code| u8::try_from(code).ok())
+            .collect()
+    }
+    /// The number of bytes in `b` when it is known
+    pub fn bytes_len(&self, b: &z3::ast::String<'ctx>) -> Option<usize> {
+        concat_parts(b)
+            .iter()
+            .map(|part| literal_codes(part).map(|codes| codes.len()))
+            .sum()
+    }
+    /// New, unknown bytes, at most `max_len` of them when it is given. Each one is a character
+    /// in 0..=0xff, as every byte is.
+    pub fn fresh_bytes(&mut self, hint: &str, max_len: Option<usize>) -> z3::ast::String<'ctx> {
+        let b = z3::ast::String::fresh_const(self.context, hint);
+        self.add_constraint(b.regex_matches(&self.any_byte().star()));
+        if let Some(max_len) = max_len {
+            let len = u32::try_from(max_len).unwrap_or(u32::MAX);
+            self.add_constraint(b.regex_matches(&self.any_char().r#loop(0, len)));
+        }
+        b
+    }
+    /// `b` cut after its first `at` bytes. `None` when a part of `b` before the cut is unknown,
+    /// it has no known length then.
+    pub fn split_bytes(
+        &self,
+        b: &z3::ast::String<'ctx>,
+        at: usize,
+    ) -> Option<(z3::ast::String<'ctx>, z3::ast::String<'ctx>)> {
+        let parts = concat_parts(b);
+        let mut at = at;
+        for (i, part) in parts.iter().enumerate() {
+            if at == 0 {
+                return Some((self.concat_all(&parts[..i]), self.concat_all(&parts[i..])));
+            }
+            let codes = literal_codes(part)?;
+            if at < codes.len() {
+                let mut left = parts[..i].to_vec();
+                left.push(self.static_chars(codes[..at].iter().copied()));
+                let mut right = vec![self.static_chars(codes[at..].iter().copied())];
+                right.extend_from_slice(&parts[i + 1..]);
+                return Some((self.concat_all(&left), self.concat_all(&right)));
+            }
+            at -= codes.len();
+        }
+        (at == 0).then(|| (b.clone(), self.static_string("")))
+    }
+    /// The bytes `start..end` of `b` (`start..` without an `end`), when they can be told apart
+    /// (see `split_bytes`)
+    pub fn slice_bytes(
+        &self,
+        b: &z3::ast::String<'ctx>,
+        start: usize,
+        end: Option<usize>,
+    ) -> Option<z3::ast::String<'ctx>> {
+        let (_, rest) = self.split_bytes(b, start)?;
+        match end {
+            Some(end) => Some(self.split_bytes(&rest, end.checked_sub(start)?)?.0),
+            None => Some(rest),
+        }
+    }
+    /// The bytes of `b` in a range they cannot be cut out at (see `split_bytes`): unknown bytes
+    /// at the `part` of `b`. The same range, with the same `bound` (its start or end), gives
+    /// the same bytes again.
+    pub fn bytes_part(
+        &mut self,
+        b: &z3::ast::String<'ctx>,
+        part: BytesPart,
+        bound: Option<&z3::ast::Int<'ctx>>,
+    ) -> z3::ast::String<'ctx> {
+        let key = bound.map(|bound| (b.clone(), part, bound.clone()));
+        if let Some(bytes) = key.as_ref().and_then(|key| self.byte_parts.get(key)) {
+            return bytes.clone();
+        }
+        // any part of repeated bytes is the same byte repeated, which the solver handles far
+        // better than a prefix, suffix or contains constraint
+        let bytes = match self.repeated_bytes.get(b) {
+            Some(&byte) => self.repeated_byte(byte),
+            None => {
+                let bytes = self.fresh_bytes("part", None);
+                self.add_constraint(match part {
+                    BytesPart::Prefix => bytes.prefix(b),
+                    BytesPart::Suffix => bytes.suffix(b),
+                    BytesPart::Inside => b.contains(&bytes),
+                });
+                bytes
+            }
+        };
+        if let Some(key) = key {
+            self.byte_parts.insert(key, bytes.clone());
+        }
+        bytes
+    }
+    /// Any number of `byte`s
+    pub fn repeated_byte(&mut self, byte: u8) -> z3::ast::String<'ctx> {
+        let b = self.fresh_bytes("bytes", None);
+        let literal = Regexp::literal(self.context, &escape([u32::from(byte)].into_iter()));
+        self.add_constraint(b.regex_matches(&literal.star()));
+        self.repeated_bytes.insert(b.clone(), byte);
+        b
+    }
+    /// `b` with `src` written over its bytes `start..end`. Without an `end` the bytes after
+    /// `start` are all replaced.
+    pub fn write_bytes(
+        &self,
+        b: &z3::ast::String<'ctx>,
+        start: usize,
+        end: Option<usize>,
+        src: &z3::ast::String<'ctx>,
+    ) -> Option<z3::ast::String<'ctx>> {
+        let (before, rest) = self.split_bytes(b, start)?;
+        let after = match end {
+            Some(end) => self.split_bytes(&rest, end.checked_sub(start)?)?.1,
+            None => self.static_string(""),
+        };
+        Some(self.concat_all(&[before, src.clone(), after]))
+    }
+    /// The text the bytes `b` decode to as UTF-8, with invalid sequences replaced when `lossy`
+    /// is set. The known parts of `b` are decoded right away, the others are linked to their
+    /// text (see `link_utf8`).
+    pub fn decode_utf8(&mut self, b: &z3::ast::String<'ctx>, lossy: bool) -> z3::ast::String<'ctx> {
+        // runs of known bytes and of unknown parts, taking turns
+        let mut runs: Vec<Result<Vec<u8>, Vec<z3::ast::String<'ctx>>>> = Vec::new();
+        for part in concat_parts(b) {
+            match (self.const_bytes(&part), runs.last_mut()) {
+                (Some(bytes), Some(Ok(run))) => run.extend(bytes),
+                (Some(bytes), _) => runs.push(Ok(bytes)),
+                (None, Some(Err(run))) => run.push(part),
+                (None, _) => runs.push(Err(vec![part])),
+            }
+        }
+        // a character split between known and unknown bytes is decoded with the unknown ones
+        let n = runs.len();
+        for i in 0..n {
+            let Ok(bytes) = &mut runs[i] else { continue };
+            let tail = if i + 1 < n {
+                bytes.split_off(bytes.len() - incomplete_tail(bytes))
+            } else {
+                Vec::new()
+            };
+            let head: Vec<u8> = if i > 0 {
+                let rest = bytes.iter().take(3).take_while(|b| *b & 0xc0 == 0x80).count();
+                bytes.drain(..rest).collect()
+            } else {
+                Vec::new()
+            };
+            if let Some(Err(next)) = runs.get_mut(i + 1) {
+                next.insert(0, self.static_bytes(&tail));
+            }
+            if let Some(Err(prev)) = i.checked_sub(1).and_then(|i| runs.get_mut(i)) {
+                prev.push(self.static_bytes(&head));
+            }
+        }
+        let mut texts = Vec::new();
+        for run in runs {
+            let text = match run {
+                Ok(bytes) if lossy => self.static_string(&String::from_utf8_lossy(&bytes)),
+                Ok(bytes) => match String::from_utf8(bytes) {
+                    Ok(text) => self.static_string(&text),
+                    // the decoding fails, there is no text
+                    Err(_) => self.fresh_string("text"),
+                },
+                Err(parts) => {
+                    let text = self.fresh_string("text");
+                    self.link_utf8(&self.concat_all(&parts), &text);
+                    text
+                }
+            };
+            texts.push(text);
+        }
+        self.concat_all(&texts)
+    }
+    /// The UTF-8 bytes of the text `s`, linked to it like in `decode_utf8`
+    pub fn encode_utf8(&mut self, s: &z3::ast::String<'ctx>) -> z3::ast::String<'ctx> {
+        let mut bytes = Vec::new();
+        for part in concat_parts(s) {
+            let text = literal_codes(&part)
+                .and_then(|codes| codes.into_iter().map(char::from_u32).collect::<Option<String>>());
+            bytes.push(match text {
+                Some(text) => self.static_bytes(text.as_bytes()),
+                None => {
+                    let b = self.fresh_bytes("bytes", None);
+                    self.link_utf8(&b, &part);
+                    b
+                }
+            });
+        }
+        self.concat_all(&bytes)
+    }
+    /// Constrains `text` to be what the unknown bytes `b` decode to. UTF-8 is ASCII for ASCII
+    /// text and nothing else, that much is kept: the text is ASCII exactly when the bytes are,
+    /// and then it is the same characters.
+    fn link_utf8(&mut self, b: &z3::ast::String<'ctx>, text: &z3::ast::String<'ctx>) {
+        let ascii = Regexp::union(
+            self.context,
+            &[
+                &Regexp::literal(self.context, &escape([0].into_iter())),
+                &Regexp::range(self.context, &'\u{1}', &'\u{7f}'),
+            ],
+        )
+        .star();
+        let b_ascii = b.regex_matches(&ascii);
+        self.add_constraint(b_ascii._eq(&text.regex_matches(&ascii)));
+        self.add_constraint(b_ascii.implies(&b._eq(text)));
+    }
+    // Matches any one character: z3 has no such regular expression to build here, but it is a
+    // non-empty string that is not the concatenation of two
+    fn any_char(&self) -> Regexp<'ctx> {
+        let nonempty = Regexp::literal(self.context, "").complement();
+        let several = Regexp::concat(self.context, &[&nonempty, &nonempty]);
+        Regexp::intersect(self.context, &[&nonempty, &several.complement()])
+    }
+    // Matches any one byte. Past ASCII a range would not do: z3 reads its bounds as UTF-8 text,
+    // which is more than one character there
+    fn any_byte(&self) -> Regexp<'ctx> {
+        let ascii = Regexp::range(self.context, &'\u{1}', &'\u{7f}');
+        let rest = (0x80..=0xff)
+            .chain([0])
+            .map(|code| Regexp::literal(self.context, &escape([code].into_iter())))
+            .collect::<Vec<_>>();
+        Regexp::union(self.context, &[&ascii].into_iter().chain(&rest).collect::<Vec<_>>())
+    }
+    // The concatenation of `parts`
+    fn concat_all(&self, parts: &[z3::ast::String<'ctx>]) -> z3::ast::String<'ctx> {
+        match parts {
+            [] => self.static_string(""),
+            [part] => part.clone(),
+            _ => z3::ast::String::concat(self.context, &parts.iter().collect::<Vec<_>>()),
+        }
+    }
+
     /// Creates a z3 bool expression representing whether or not two strings are equivalent.
     pub fn string_eq(
         &self,
@@ -279,7 +735,7 @@ impl<'ctx> SymExecBool<'ctx> {
         lhs: &z3::ast::Bool<'ctx>,
         rhs: &z3::ast::Bool<'ctx>,
     ) -> z3::ast::Bool<'ctx> {
-        z3::ast::Bool::from_bool(&self.context, true)
+        z3::ast::Bool::from_bool(self.context, true)
     }
     /// Create a z3 bool expression from the less than or equal comparison of two z3 bool expressions.
     pub fn bool_gt(&self, a: &z3::ast::Bool<'ctx>, b: &z3::ast::Bool<'ctx>) -> z3::ast::Bool<'ctx> {
@@ -291,7 +747,7 @@ impl<'ctx> SymExecBool<'ctx> {
         lhs: &z3::ast::Bool<'ctx>,
         rhs: &z3::ast::Bool<'ctx>,
     ) -> z3::ast::Bool<'ctx> {
-        z3::ast::Bool::from_bool(&self.context, true)
+        z3::ast::Bool::from_bool(self.context, true)
     }
 
     /// Creates a z3 int expression from an Rust int.
@@ -300,7 +756,7 @@ impl<'ctx> SymExecBool<'ctx> {
             z3::ast::Int::from_i64(self.context, v as i64)
         } else {
             let neg = v < 0;
-            let u: u128 = v.abs() as u128;
+            let u: u128 = v.unsigned_abs();
             let lo = (u & 0xFFFFFFFFFFFFFFFF) as u64;
             let hi = (u >> 64) as u64;
             let lo_i = z3::ast::Int::from_u64(self.context, lo);
@@ -317,6 +773,13 @@ impl<'ctx> SymExecBool<'ctx> {
             }
         }
     }
+    /// Creates a z3 int expression from an unsigned Rust int.
+    pub fn static_uint(&self, v: u128) -> z3::ast::Int<'ctx> {
+        match i128::try_from(v) {
+            Ok(v) => self.static_int(v),
+            Err(_) => z3::ast::Int::from_str(self.context, &v.to_string()).unwrap(),
+        }
+    }
     /// Creates a z3 int expression from the addition of two z3 int expressions.
     pub fn add(&self, a: &z3::ast::Int<'ctx>, b: &z3::ast::Int<'ctx>) -> z3::ast::Int<'ctx> {
         z3::ast::Int::add(self.context, &[a, b])
@@ -358,27 +821,114 @@ impl<'ctx> SymExecBool<'ctx> {
         a.ge(b)
     }
 
-    pub fn int_interval(&self, v: &str) -> (Option<i128>, Option<i128>) {
-        self.interval_map.get(v).cloned().unwrap_or((None, None))
+    /// `a`, a value of type `ty`, as a bit-vector. A value `from_bv` made gives back its bits
+    /// and a constant is converted right away, the solver is slow at the conversion.
+    pub fn to_bv(&self, a: &z3::ast::Int<'ctx>, ty: IntTy) -> z3::ast::BV<'ctx> {
+        if a.safe_decl().is_ok_and(|d| d.kind() == z3::DeclKind::BV2INT)
+            && let Some(bits) = a.children().first().and_then(|c| c.as_bv())
+            && bits.get_size() == ty.bits
+        {
+            return bits;
+        }
+        let bv = z3::ast::BV::from_int(a, ty.bits);
+        if a.is_const() { bv.simplify() } else { bv }
     }
-    pub fn set_interval(&mut self, v: &str, lo: Option<i128>, hi: Option<i128>) {
-        self.interval_map.insert(v.into(), (lo, hi));
+    /// The bits of `b` as an integer. They are read as an unsigned number whatever the type,
+    /// the operations give them their sign.
+    pub fn from_bv(&self, b: &z3::ast::BV<'ctx>) -> z3::ast::Int<'ctx> {
+        b.to_int(false)
     }
-    pub fn widen(
-        (l0, h0): (Option<i128>, Option<i128>),
-        (l1, h1): (Option<i128>, Option<i128>),
-    ) -> (Option<i128>, Option<i128>) {
-        (
-            if l0 == l1 { l0 } else { None },
-            if h0 == h1 { h0 } else { None },
-        )
+    /// A new, unconstrained value of type `ty`
+    pub fn fresh_int_of(&self, name: &str, ty: IntTy) -> z3::ast::Int<'ctx> {
+        self.from_bv(&z3::ast::BV::fresh_const(self.context, name, ty.bits))
+    }
+    /// Applies the bit-vector operation `op` to `a` and `b`, two values of type `ty`
+    pub fn bv_op(
+        &self,
+        a: &z3::ast::Int<'ctx>,
+        b: &z3::ast::Int<'ctx>,
+        ty: IntTy,
+        op: impl Fn(&z3::ast::BV<'ctx>, &z3::ast::BV<'ctx>) -> z3::ast::BV<'ctx>,
+    ) -> z3::ast::Int<'ctx> {
+        self.from_bv(&op(&self.to_bv(a, ty), &self.to_bv(b, ty)))
+    }
+    /// Compares `a` and `b`, two values of type `ty`, with the bit-vector predicate `op`
+    pub fn bv_cmp(
+        &self,
+        a: &z3::ast::Int<'ctx>,
+        b: &z3::ast::Int<'ctx>,
+        ty: IntTy,
+        op: impl Fn(&z3::ast::BV<'ctx>, &z3::ast::BV<'ctx>) -> z3::ast::Bool<'ctx>,
+    ) -> z3::ast::Bool<'ctx> {
+        op(&self.to_bv(a, ty), &self.to_bv(b, ty))
+    }
+    /// Whether `op` on `a` and `b`, two values of type `ty`, has a result that does not fit
+    /// `ty`: it is computed at twice the width, where no addition, subtraction or
+    /// multiplication can overflow
+    pub fn bv_overflows(
+        &self,
+        a: &z3::ast::Int<'ctx>,
+        b: &z3::ast::Int<'ctx>,
+        ty: IntTy,
+        op: impl Fn(&z3::ast::BV<'ctx>, &z3::ast::BV<'ctx>) -> z3::ast::BV<'ctx>,
+    ) -> z3::ast::Bool<'ctx> {
+        let widen = |x: &z3::ast::BV<'ctx>| match ty.signed {
+            true => x.sign_ext(ty.bits),
+            false => x.zero_ext(ty.bits),
+        };
+        let wide = op(&widen(&self.to_bv(a, ty)), &widen(&self.to_bv(b, ty)));
+        widen(&wide.extract(ty.bits - 1, 0))._eq(&wide).not()
+    }
+    /// `a` shifted by `b` as Rust shifts a value of type `ty` by one of type `amount_ty`: the
+    /// amount is taken modulo the width, and a right shift of a signed value is arithmetic
+    pub fn shift(
+        &self,
+        a: &z3::ast::Int<'ctx>,
+        b: &z3::ast::Int<'ctx>,
+        ty: IntTy,
+        amount_ty: IntTy,
+        left: bool,
+    ) -> z3::ast::Int<'ctx> {
+        let amount = self.to_bv(b, amount_ty);
+        let amount = match amount_ty.bits.cmp(&ty.bits) {
+            std::cmp::Ordering::Less => amount.zero_ext(ty.bits - amount_ty.bits),
+            std::cmp::Ordering::Equal => amount,
+            std::cmp::Ordering::Greater => amount.extract(ty.bits - 1, 0),
+        };
+        let amount = amount.bvurem(&z3::ast::BV::from_u64(self.context, ty.bits.into(), ty.bits));
+        let a = self.to_bv(a, ty);
+        let shifted = match (left, ty.signed) {
+            (true, _) => a.bvshl(&amount),
+            (false, true) => a.bvashr(&amount),
+            (false, false) => a.bvlshr(&amount),
+        };
+        self.from_bv(&shifted)
+    }
+    /// `a as to` for `a` of type `from`: truncated, or sign- or zero-extended as `from` is
+    pub fn cast_int(&self, a: &z3::ast::Int<'ctx>, from: IntTy, to: IntTy) -> z3::ast::Int<'ctx> {
+        let a = self.to_bv(a, from);
+        let cast = match from.bits.cmp(&to.bits) {
+            std::cmp::Ordering::Greater => a.extract(to.bits - 1, 0),
+            std::cmp::Ordering::Equal => a,
+            std::cmp::Ordering::Less if from.signed => a.sign_ext(to.bits - from.bits),
+            std::cmp::Ordering::Less => a.zero_ext(to.bits - from.bits),
+        };
+        self.from_bv(&cast)
+    }
+
+    /// The values `v` can have, as far as the pre-pass knows
+    pub fn int_interval(&self, v: &str) -> Interval {
+        self.interval_map.get(v).copied().unwrap_or(Interval::TOP)
+    }
+    pub fn set_interval(&mut self, v: &str, interval: Interval) {
+        self.interval_map.insert(v.into(), interval);
     }
 
     // Creates a z3 regular expression from a pattern string.
     /// The pattern can contain '*' as a wildcard that matches any sequence of characters.
     fn regex_from_pattern(&self, pat: &str) -> z3::ast::Regexp<'ctx> {
         if !pat.contains('*') {
-            return Regexp::literal(self.context, pat);
+            return Regexp::literal(self.context, &escape(pat.chars().map(u32::from)));
         }
         let mut parts = Vec::new();
         for (i, seg) in pat.split('*').enumerate() {
@@ -386,7 +936,7 @@ impl<'ctx> SymExecBool<'ctx> {
                 parts.push(Regexp::full(self.context));
             }
             if !seg.is_empty() {
-                parts.push(Regexp::literal(self.context, seg));
+                parts.push(Regexp::literal(self.context, &escape(seg.chars().map(u32::from))));
             }
         }
         let refs: Vec<&Regexp<'ctx>> = parts.iter().collect();
@@ -396,18 +946,60 @@ impl<'ctx> SymExecBool<'ctx> {
             _ => Regexp::concat(self.context, &refs),
         }
     }
+    // The spellings of the paths matching `pattern` (see `resolves_to`), one after the other: a
+    // root, where `..` stays, then the components, with separators of slashes and `.` segments
+    // and detours through other directories before each one. Whether the pattern has the
+    // `self` of `/proc/self` comes with them.
+    fn path_parts(&self, pattern: &str) -> (Vec<z3::ast::Regexp<'ctx>>, bool) {
+        let ctx = self.context;
+        let lit = |s: &str| Regexp::literal(ctx, s);
+        let more = Regexp::union(ctx, &[&lit("/"), &lit("./")]).star();
+        let sep = Regexp::concat(ctx, &[&lit("/"), &more]);
+        let up = Regexp::concat(ctx, &[&lit(".."), &sep]);
+        let full = Regexp::full(ctx);
+        let slash_free = Regexp::concat(ctx, &[&full, &lit("/"), &full]).complement();
+        let not_dots = Regexp::union(ctx, &[&lit(""), &lit("."), &lit("..")]).complement();
+        let name = Regexp::intersect(ctx, &[&slash_free, &not_dots]);
+        let mut detour = Regexp::empty(ctx);
+        for _ in 0..DETOUR_DEPTH {
+            detour = Regexp::concat(ctx, &[&name, &sep, &detour.star(), &up]);
+        }
+        let mut parts = vec![sep.clone(), Regexp::union(ctx, &[&up, &detour]).star()];
+        let mut proc_self = false;
+        let components: Vec<&str> = pattern.split('/').filter(|c| !c.is_empty()).collect();
+        for (i, component) in components.iter().enumerate() {
+            if i > 0 {
+                parts.push(sep.clone());
+                parts.push(detour.star());
+            }
+            if i == 1 && components[0] == "proc" && *component == "self" {
+                proc_self = true;
+                parts.push(Regexp::union(ctx, &[&lit("self"), &lit("thread-self")]));
+            } else {
+                parts.push(self.regex_from_pattern(component));
+            }
+        }
+        // a trailing slash is part of the pattern, `/etc/` is a directory
+        if !components.is_empty() && pattern.ends_with('/') {
+            parts.push(sep);
+        }
+        (parts, proc_self)
+    }
+    /// The id of the process being analyzed (`std::process::id()`), a positive number
+    pub fn process_id(&self) -> z3::ast::Int<'ctx> {
+        z3::ast::Int::new_const(self.context, PROCESS_ID)
+    }
+    /// The decimal digits of `process_id`, as in `/proc/<pid>`
+    pub fn process_id_digits(&self) -> z3::ast::String<'ctx> {
+        z3::ast::String::new_const(self.context, PROCESS_ID_DIGITS)
+    }
     /// Checks if the given string matches the given pattern.
     pub fn check_string_matches(
         &self,
         expr: &z3::ast::String<'ctx>,
         pattern: &str,
     ) -> z3::SatResult {
-        let s = z3::Solver::new(self.context);
-        for c in &self.constraints {
-            s.assert(c);
-        }
-        s.assert(&expr.regex_matches(&self.regex_from_pattern(pattern)));
-        s.check()
+        self.check_constraint_sat(&self.matches_pattern(expr, pattern))
     }
 
     /// UNSAT if it's impossible for expr to NOT match the pattern
@@ -416,11 +1008,88 @@ impl<'ctx> SymExecBool<'ctx> {
         expr: &z3::ast::String<'ctx>,
         pattern: &str,
     ) -> z3::SatResult {
-        let re = self.regex_from_pattern(pattern);
-        let matches = expr.regex_matches(&re);
+        let matches = self.matches_pattern(expr, pattern);
         self.check_constraint_sat(&self.not(&matches))
     }
 
+    /// Whether `expr` is `pattern`, where a `*` in the pattern matches any sequence of characters,
+    /// or resolves to it when it is an absolute path (see `resolves_to`)
+    pub fn matches_pattern(&self, expr: &z3::ast::String<'ctx>, pattern: &str) -> z3::ast::Bool<'ctx> {
+        if pattern.starts_with('/') {
+            return self.resolves_to(expr, pattern);
+        }
+        expr.regex_matches(&self.regex_from_pattern(pattern))
+    }
+
+    /// The value of the variable `name` when it is an input, i.e. an unknown the path has not
+    /// computed anything from (a parameter, or data read from a source)
+    pub fn input_value(&self, name: &str) -> Option<z3::ast::Dynamic<'ctx>> {
+        let value = self
+            .string_variables
+            .get(name)
+            .map(|s| z3::ast::Dynamic::from_ast(&s.value))
+            .or_else(|| self.int_variables.get(name).map(|s| z3::ast::Dynamic::from_ast(&s.value)))
+            .or_else(|| self.bool_variables.get(name).map(|s| z3::ast::Dynamic::from_ast(&s.value)))?;
+        let uninterpreted = value.is_const()
+            && value.safe_decl().is_ok_and(|d| d.kind() == z3::DeclKind::UNINTERPRETED);
+        uninterpreted.then_some(value)
+    }
+
+    /// The inputs (see `input_value`) `expr` or the path constraints are computed from
+    pub fn inputs_of(&self, expr: &z3::ast::Dynamic<'ctx>) -> Vec<z3::ast::Dynamic<'ctx>> {
+        let mut inputs = Vec::new();
+        let mut todo = vec![expr.clone()];
+        todo.extend(self.constraints.iter().map(|c| z3::ast::Dynamic::from_ast(c)));
+        while let Some(e) = todo.pop() {
+            if e.is_const() {
+                let uninterpreted =
+                    e.safe_decl().is_ok_and(|d| d.kind() == z3::DeclKind::UNINTERPRETED);
+                if uninterpreted && !inputs.contains(&e) {
+                    inputs.push(e);
+                }
+            } else {
+                todo.extend(e.children());
+            }
+        }
+        inputs
+    }
+
+    /// Values of `exprs` in a model of the path constraints and `cond`, written as Rust literals.
+    /// `None` when there is no such model.
+    pub fn model_values(
+        &self,
+        cond: &z3::ast::Bool<'ctx>,
+        exprs: &[(String, z3::ast::Dynamic<'ctx>)],
+    ) -> Option<Vec<(String, String)>> {
+        let mut solver = self.solver.borrow_mut();
+        solver.check_with(&self.constraints, Some(cond), |result, s| {
+            if result != z3::SatResult::Sat {
+                return None;
+            }
+            let model = s.get_model()?;
+            let mut values = Vec::new();
+            for (name, expr) in exprs {
+                let value = model.eval(expr, true)?;
+                let codes = value.as_string().and_then(|v| literal_codes(&v));
+                let literal = if let Some(codes) = codes {
+                    let text = codes
+                        .into_iter()
+                        .map(|code| char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER))
+                        .collect::<String>();
+                    format!("{text:?}")
+                } else if let Some(v) = value.as_int().and_then(|v| v.as_i64()) {
+                    v.to_string()
+                } else if let Some(v) = value.as_bool().and_then(|v| v.as_bool()) {
+                    v.to_string()
+                } else {
+                    continue;
+                };
+                values.push((name.clone(), literal));
+            }
+            Some(values)
+        })
+    }
+
     /// SAT if there exists a model with s == lit
     pub fn could_equal_literal(&self, s: &z3::ast::String<'ctx>, lit: &str) -> z3::SatResult {
         let eq = s._eq(&self.static_string(lit));
@@ -458,11 +1127,59 @@ impl<'ctx> SymExecBool<'ctx> {
             self.string_variables
                 .insert(name.into(), Slot::with_flag(s, flag));
         }
+        if !flag {
+            self.taint_origins.remove(name);
+        }
+    }
+
+    /// Taints `name` with data that got there through `span`, after the steps in `prev`
+    pub fn taint_with_step(
+        &mut self,
+        name: &str,
+        span: Span,
+        message: String,
+        prev: Option<Rc<TaintStep>>,
+    ) {
+        self.set_taint(name, true);
+        let step = TaintStep {
+            span,
+            message,
+            prev,
+        };
+        self.taint_origins.insert(name.into(), Rc::new(step));
+    }
+
+    /// The last step of how the taint of `name` got there, if it is tainted and that is known
+    pub fn taint_origin(&self, name: &str) -> Option<Rc<TaintStep>> {
+        self.taint_origins.get(name).cloned()
+    }
+
+    /// Taints every variable that is tainted in `other`, and the path if it is tainted there.
+    pub fn join_taint(&mut self, other: &Self) {
+        fn tainted<T>(map: &HashMap<String, Slot<T>>) -> impl Iterator<Item = &String> {
+            map.iter().filter(|(_, s)| s.flag).map(|(k, _)| k)
+        }
+        let names: Vec<String> = tainted(&other.string_variables)
+            .chain(tainted(&other.int_variables))
+            .chain(tainted(&other.bool_variables))
+            .cloned()
+            .collect();
+        for name in names {
+            self.set_taint(&name, true);
+            if let Some(origin) = other.taint_origin(&name) {
+                self.taint_origins.entry(name).or_insert(origin);
+            }
+        }
+        self.path_taint |= other.path_taint;
     }
 
     /// Copy taint from `src` → `dest` (used by the MIR interpreter).
     pub fn propagate_taint(&mut self, src: &str, dest: &str) {
         self.set_taint(dest, self.is_tainted(src));
+        match self.taint_origin(src) {
+            Some(origin) => self.taint_origins.insert(dest.into(), origin),
+            None => self.taint_origins.remove(dest),
+        };
     }
     pub fn dump_taint(&self) {
         println!("─── TAINT STATUS ───");
@@ -487,17 +1204,17 @@ impl<'ctx> SymExecBool<'ctx> {
     /// Creating a fresh Z3 string gives us an unconstrained placeholder that can later
     /// be restricted by constraints. And using a stable key is nice because it ensures
     /// that we always refer to the same symbolic value when updating constraints.
-
+    ///
     /// # When to call this
     /// - When you need a string value for a destination place but cannot extract one from
     ///   the operand (no constant, no tracked string in the source).
     /// - Currenlty we use this for String::from_utf8_lossy but I suppose could be usefull in cases like
-    /// FFI boundaries and deselerizers?
+    ///   FFI boundaries and deselerizers?
     pub fn get_or_fresh_string(&mut self, key: &str) -> z3::ast::String<'ctx> {
         if let Some(s) = self.get_string(key).cloned() {
             return s;
         }
-        let s = z3::ast::String::fresh_const(&self.context, key);
+        let s = z3::ast::String::fresh_const(self.context, key);
         self.assign_string(key, s.clone());
         s
     }
@@ -537,3 +1254,162 @@ fn test_static_int() {
     assert_eq!(c.to_string(), "9223372036854775802");
     assert!(exec.check_constraint_sat(&exec.int_lt(&d, &e)) == z3::SatResult::Sat);
 }
+
+#[test]
+fn test_bit_precise() {
+    let cfg = z3::Config::new();
+    let ctx = z3::Context::new(&cfg);
+    let exec = SymExecBool::new(&ctx);
+    let u8_ty = IntTy { bits: 8, signed: false };
+    let i8_ty = IntTy { bits: 8, signed: true };
+    let u32_ty = IntTy { bits: 32, signed: false };
+    // values are the bits of the result, read as an unsigned number
+    let eval = |e: &z3::ast::Int| e.simplify().as_i64().unwrap();
+    let int = |v: i128| exec.static_int(v);
+    assert_eq!(eval(&exec.bv_op(&int(250), &int(7), u8_ty, |a, b| a.bvadd(b))), 1);
+    assert_eq!(eval(&exec.bv_op(&int(-7), &int(2), i8_ty, |a, b| a.bvsdiv(b))), 0xfd);
+    assert_eq!(eval(&exec.bv_op(&int(0xf0), &int(0x3c), u8_ty, |a, b| a.bvand(b))), 0x30);
+    assert_eq!(eval(&exec.shift(&int(-128), &int(9), i8_ty, u32_ty, false)), 0xc0);
+    assert_eq!(eval(&exec.cast_int(&int(-1), i8_ty, u32_ty)), 0xffff_ffff);
+    assert_eq!(eval(&exec.cast_int(&int(0x1ff), u32_ty, i8_ty)), 0xff);
+    let overflows = exec.bv_overflows(&int(100), &int(27), i8_ty, |a, b| a.bvadd(b));
+    assert_eq!(overflows.simplify().as_bool(), Some(false));
+    let overflows = exec.bv_overflows(&int(100), &int(28), i8_ty, |a, b| a.bvadd(b));
+    assert_eq!(overflows.simplify().as_bool(), Some(true));
+    // a fresh value stays in the range of its type
+    let x = exec.fresh_int_of("x", i8_ty);
+    let below = exec.bv_cmp(&x, &int(-128), i8_ty, |a, b| a.bvslt(b));
+    assert!(exec.check_constraint_sat(&below) == z3::SatResult::Unsat);
+    assert!(exec.check_constraint_sat(&x.gt(&int(255))) == z3::SatResult::Unsat);
+}
+
+#[test]
+fn test_taint_origin() {
+    let cfg = z3::Config::new();
+    let ctx = z3::Context::new(&cfg);
+    let mut exec = SymExecBool::new(&ctx);
+    exec.create_uninterpreted_string("1");
+    exec.create_uninterpreted_string("2");
+    exec.taint_with_step("1", rustc_span::DUMMY_SP, "source".into(), None);
+    let source = exec.taint_origin("1");
+    exec.taint_with_step("1", rustc_span::DUMMY_SP, "source".into(), source);
+    exec.propagate_taint("1", "2");
+    assert!(exec.is_tainted("2"));
+    // the repeated step is only listed once
+    assert_eq!(exec.taint_origin("2").unwrap().steps().len(), 1);
+    exec.set_taint("2", false);
+    assert!(exec.taint_origin("2").is_none());
+}
+
+#[test]
+fn test_bytes() {
+    let cfg = z3::Config::new();
+    let ctx = z3::Context::new(&cfg);
+    let mut exec = SymExecBool::new(&ctx);
+    let data = exec.static_bytes(b"/data/\\\xff");
+    assert_eq!(exec.const_bytes(&data).unwrap(), b"/data/\\\xff");
+    // bytes from the network after a known prefix
+    let read = exec.fresh_bytes("read", Some(64));
+    let buf = exec.concat_strings(&exec.static_bytes(b"/data/"), &read);
+    assert_eq!(exec.bytes_len(&buf), None);
+    let dir = exec.slice_bytes(&buf, 1, Some(5)).unwrap();
+    assert_eq!(exec.const_bytes(&dir).unwrap(), b"data");
+    let tmp = exec.write_bytes(&buf, 0, Some(6), &exec.static_bytes(b"/tmp/")).unwrap();
+    assert_eq!(exec.bytes_len(&exec.split_bytes(&tmp, 5).unwrap().0), Some(5));
+    // the prefix carries over to the text
+    let text = exec.decode_utf8(&buf, false);
+    assert!(exec.could_equal_literal(&text, "/proc/self/mem") == z3::SatResult::Unsat);
+    assert!(exec.could_equal_literal(&text, "/data/mem") == z3::SatResult::Sat);
+    let read = exec.decode_utf8(&read, true);
+    assert!(exec.could_equal_literal(&read, &"a".repeat(65)) == z3::SatResult::Unsat);
+    // each unknown byte is one, in a model as well
+    let byte = exec.fresh_bytes("byte", Some(1));
+    assert!(exec.could_equal_literal(&byte, "\u{ff}") == z3::SatResult::Sat);
+    assert!(exec.could_equal_literal(&byte, "\u{17a}") == z3::SatResult::Unsat);
+    let ascii = Regexp::range(&ctx, &'\u{1}', &'\u{7f}').star();
+    let high = exec.not(&byte.regex_matches(&ascii));
+    let high = exec.and(&high, &exec.not(&byte._eq(&exec.static_chars([0].into_iter()))));
+    let model = exec.solver.borrow_mut().check_with(&exec.constraints, Some(&high), |_, s| {
+        literal_codes(&s.get_model()?.eval(&byte, true)?)
+    });
+    assert!(model.is_some_and(|codes| matches!(codes[..], [0x80..=0xff])));
+    // a character split between known and unknown bytes
+    let e = exec.fresh_bytes("e", Some(1));
+    let split = exec.concat_strings(&exec.static_bytes(b"/d\xc3"), &e);
+    let text = exec.decode_utf8(&split, true);
+    assert!(exec.could_equal_literal(&text, "/d\u{e9}") == z3::SatResult::Sat);
+    // and a model of it is written with that character
+    let eq = text._eq(&exec.static_string("/d\u{e9}"));
+    let values = exec.model_values(&eq, &[("text".into(), z3::ast::Dynamic::from_ast(&text))]);
+    assert_eq!(values.unwrap(), [("text".to_string(), "\"/d\u{e9}\"".to_string())]);
+    let text = exec.decode_utf8(&split, false);
+    assert!(exec.could_equal_literal(&text, "/proc/self/mem") == z3::SatResult::Unsat);
+    // and back
+    let text = exec.decode_utf8(&buf, false);
+    let bytes = exec.encode_utf8(&text);
+    let prefix = exec.static_bytes(b"/data/").prefix(&bytes);
+    assert!(exec.check_constraint_sat(&exec.not(&prefix)) == z3::SatResult::Unsat);
+}
+
+#[test]
+fn test_incremental_solver() {
+    let cfg = z3::Config::new();
+    let ctx = z3::Context::new(&cfg);
+    let mut exec = SymExecBool::new(&ctx);
+    exec.create_int("x");
+    let x = exec.get_int("x").unwrap().clone();
+    exec.add_constraint(x.gt(&exec.static_int(5)));
+    // both branches of `x < 3` share the solver and the constraint on x
+    let mut then = exec.clone();
+    then.add_constraint(x.lt(&exec.static_int(3)));
+    let mut otherwise = exec.clone();
+    otherwise.add_constraint(exec.not(&x.lt(&exec.static_int(3))));
+    assert!(then.check_sat() == z3::SatResult::Unsat);
+    assert!(otherwise.check_sat() == z3::SatResult::Sat);
+    assert!(then.check_sat() == z3::SatResult::Unsat);
+    let seven = x._eq(&exec.static_int(7));
+    assert!(exec.check_constraint_sat(&seven) == z3::SatResult::Sat);
+    assert_eq!(exec.solver.borrow().asserted.len(), 1);
+}
+
+#[test]
+fn test_resolves_to() {
+    let cfg = z3::Config::new();
+    let ctx = z3::Context::new(&cfg);
+    let mut exec = SymExecBool::new(&ctx);
+    let resolves = |path: &str, pattern: &str| {
+        let cond = exec.resolves_to(&exec.static_string(path), pattern);
+        exec.check_constraint_sat(&cond) == z3::SatResult::Sat
+    };
+    let mem = "/proc/self/mem";
+    assert!(resolves("/proc/self/mem", mem));
+    assert!(resolves("/proc/../proc/self/mem", mem));
+    assert!(resolves("//proc/./thread-self//mem", mem));
+    assert!(resolves("/tmp/a/b/../../../proc/self/mem", mem));
+    assert!(!resolves("/proc/self/../mem", mem));
+    assert!(!resolves("/proc/self/memx", mem));
+    assert!(!resolves("proc/self/mem", mem));
+    // a trailing slash stays
+    assert!(resolves("/etc/", "/etc/"));
+    assert!(resolves("/tmp/..//etc/./", "/etc/"));
+    assert!(!resolves("/etc", "/etc/"));
+    // a `*` matches whole components, or several
+    assert!(resolves("/proc/1/mem", "/proc/*/mem"));
+    assert!(resolves("/tmp/../etc/ssh/sshd_config", "/etc/*"));
+    assert!(!resolves("/etc", "/etc/*"));
+    assert!(!resolves("/proc/1/maps", "/proc/*/mem"));
+    // a number is another process, only the digits of this one's id stand for `self`
+    assert!(!resolves("/../proc/1234/mem", mem));
+    let own = exec.concat_strings(&exec.static_string("/proc/"), &exec.process_id_digits());
+    let own = exec.concat_strings(&own, &exec.static_string("/mem"));
+    assert!(exec.check_string_matches(&own, mem) == z3::SatResult::Sat);
+    assert!(exec.check_string_always_matches(&own, mem) == z3::SatResult::Sat);
+    assert!(exec.check_string_always_matches(&own, "/proc/*/mem") == z3::SatResult::Unsat);
+    let cond = exec.resolves_to(&own, "/proc/1/mem");
+    assert!(exec.check_constraint_sat(&cond) == z3::SatResult::Unsat);
+    // a path with an unknown part can be written to resolve to it
+    exec.create_uninterpreted_string("dir");
+    let path = exec.concat_strings(exec.get_string("dir").unwrap(), &exec.static_string("/mem"));
+    let cond = exec.resolves_to(&path, "/proc/*");
+    assert!(exec.check_constraint_sat(&cond) == z3::SatResult::Sat);
+}
@@ -9,7 +9,7 @@ ROOT_DIR = Path(os.getcwd())
 EXAMPLES_DIR = ROOT_DIR / "examples" / "crates"
 RESULTS_CSV  = EXAMPLES_DIR / "results.csv"
 LHS_BIN      = ROOT_DIR / "target" / "debug" / "lhs"
-CSV_NAME     = "dangerous_spans.csv"
+CARGO_LHS    = ROOT_DIR / "target" / "debug" / "cargo-lhs"
 
 def run(cmd, cwd=None):
     return subprocess.run(
@@ -25,29 +25,12 @@ def build_lhs():
         print(f"[ERROR] Failed to build lhs: {result.stdout}")
         sys.exit(1)
 
-def crate_hit(crate_dir: Path) -> bool:
-    csv_path = crate_dir / CSV_NAME
-    if not csv_path.exists():
-        return False
-    try:
-        with csv_path.open() as f:
-            return sum(1 for _ in f) > 1
-    except Exception:
-        return False
-
-def cleanup(crate_dir: Path):
-    for p in [crate_dir / CSV_NAME, crate_dir / ".cargo" / "config.toml"]:
-        try:
-            if p.exists():
-                p.unlink()
-        except Exception:
-            pass
-    cargo_dir = crate_dir / ".cargo"
-    if cargo_dir.exists() and not any(cargo_dir.iterdir()):
-        try:
-            cargo_dir.rmdir()
-        except Exception:
-            pass
+def crate_hit(report: str) -> bool:
+    # `cargo lhs` ends its report with "<N> finding(s) in <M> package(s)"
+    for line in reversed(report.splitlines()):
+        if "finding(s) in" in line:
+            return int(line.split()[0]) > 0
+    return False
 
 def eval_group(group: str, writer: csv.writer, summary: dict):
     group_dir = EXAMPLES_DIR / group
@@ -58,16 +41,9 @@ def eval_group(group: str, writer: csv.writer, summary: dict):
     for crate_dir in sorted(p for p in group_dir.iterdir() if p.is_dir()):
         crate_name = crate_dir.name
 
-        cfg_dir = crate_dir / ".cargo"
-        cfg_dir.mkdir(parents=True, exist_ok=True)
-        (cfg_dir / "config.toml").write_text(
-            f'[build]\nrustc-wrapper = "{LHS_BIN}"\n', encoding="utf-8"
-        )
+        report = run([str(CARGO_LHS), "lhs"], cwd=crate_dir).stdout
 
-        run(["cargo", "clean"], cwd=crate_dir)
-        _ = run(["cargo", "build"], cwd=crate_dir).stdout
-
-        hit = crate_hit(crate_dir)
+        hit = crate_hit(report)
         result = ("FAIL", "PASS")[hit] if group == "unsafe" else ("PASS", "FAIL")[hit]
 
         print(f"[INFO] {group}: {crate_name} → {result}")
@@ -80,8 +56,6 @@ def eval_group(group: str, writer: csv.writer, summary: dict):
         else:
             summary["failed"].append((group, crate_name))
 
-        cleanup(crate_dir)
-
 def main():
     # Verify that the LHS binary will exist after build
     if not ROOT_DIR.exists():
@@ -90,10 +64,11 @@ def main():
     
     build_lhs()
     
-    # Verify LHS binary was built successfully
-    if not LHS_BIN.exists():
-        print(f"[ERROR] LHS binary not found at: {LHS_BIN}")
-        sys.exit(1)
+    # Verify LHS binaries were built successfully
+    for binary in (LHS_BIN, CARGO_LHS):
+        if not binary.exists():
+            print(f"[ERROR] LHS binary not found at: {binary}")
+            sys.exit(1)
     
     EXAMPLES_DIR.mkdir(parents=True, exist_ok=True)
 